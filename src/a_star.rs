@@ -1,81 +1,250 @@
-use crate::direction::Direction;
+#![allow(clippy::needless_return, clippy::redundant_field_names)]
+
 use crate::matrix::Matrix;
 use crate::point::Point;
 use crate::snake::Snake;
+use crate::topology::Topology;
 
-use std::collections::HashSet;
-use std::f32;
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
 
-pub fn a_star_path(end: &Point, snake: &Snake, width: usize, height: usize) -> Option<Vec<Point>> {
-    let mut snake_pts: Matrix<bool> = Matrix::new(width, height);
+/// Estimate of the remaining cost from a cell to the goal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Heuristic {
+    /// Grid distance, exact on an empty board and never an overestimate.
+    Manhattan,
+    /// No estimate at all, which makes the search Dijkstra's algorithm.
+    Zero,
+    /// Manhattan distance times the weight. Expands fewer nodes, but the
+    /// path found can be longer than the shortest.
+    Weighted(u32),
+}
 
-    snake.for_each_segment(|pt, _| {
-        snake_pts.set_pt(&pt, true);
-    });
+impl Heuristic {
+    /// Parses `manhattan`, `zero` or `weighted:N`.
+    pub fn from_name(name: &str) -> Option<Heuristic> {
+        match name {
+            "manhattan" => Some(Heuristic::Manhattan),
+            "zero" | "dijkstra" => Some(Heuristic::Zero),
+            _ => {
+                let weight = name.strip_prefix("weighted:")?.parse().ok()?;
+                Some(Heuristic::Weighted(weight))
+            }
+        }
+    }
+
+    /// Estimate for a goal `manhattan` steps away on an empty board.
+    fn estimate(&self, manhattan: u32) -> u32 {
+        match self {
+            Heuristic::Manhattan => manhattan,
+            Heuristic::Zero => 0,
+            Heuristic::Weighted(weight) => manhattan.saturating_mul(*weight),
+        }
+    }
+}
 
-    let mut came_from: Matrix<Point> = Matrix::new(width, height);
-    let mut g_scores: Matrix<f32> = Matrix::new(width, height);
-    g_scores.set_pt(snake.get_head(), 0.0);
-    let mut f_scores: Matrix<f32> = Matrix::new(width, height);
-    f_scores.set_pt(snake.get_head(), distance(snake.get_head(), end));
+/// Which of several open cells with the same score is expanded first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TieBreak {
+    /// Furthest from the start, which heads straight for the goal on open
+    /// ground.
+    Deepest,
+    /// Closest to the start.
+    Shallowest,
+    /// Most recently opened.
+    Newest,
+    /// Least recently opened.
+    Oldest,
+}
 
-    let mut open_set: HashSet<Point> = HashSet::new();
-    open_set.insert(*snake.get_head());
+impl TieBreak {
+    pub fn from_name(name: &str) -> Option<TieBreak> {
+        match name {
+            "deepest" => Some(TieBreak::Deepest),
+            "shallowest" => Some(TieBreak::Shallowest),
+            "newest" => Some(TieBreak::Newest),
+            "oldest" => Some(TieBreak::Oldest),
+            _ => None,
+        }
+    }
+}
 
-    while open_set.len() != 0 {
-        let current = min_f_score(&open_set, &f_scores).expect("current not found?");
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchConfig {
+    pub heuristic: Heuristic,
+    pub tie_break: TieBreak,
+}
 
-        if &current == end {
-            return Some(reconstruct_path(&current, &came_from));
+impl Default for SearchConfig {
+    fn default() -> SearchConfig {
+        SearchConfig {
+            heuristic: Heuristic::Manhattan,
+            tie_break: TieBreak::Deepest,
         }
+    }
+}
 
-        open_set.remove(&current);
+/// Outcome of a single search.
+pub struct SearchResult {
+    /// Path from start to end, both included, if there is one.
+    pub path: Option<Vec<Point>>,
+    /// Cells taken off the open set and expanded.
+    pub expanded: u64,
+}
 
-        let current_g_score = g_scores.get_pt(&current).cloned();
+/// Running totals over many searches.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SearchStats {
+    pub searches: u64,
+    pub expanded: u64,
+    /// Steps along every path found.
+    pub path_steps: u64,
+    pub paths_found: u64,
+}
 
-        for dir in Direction::all() {
-            let neighbor_pt = current.dir_adj(dir);
-            if neighbor_pt.x >= width as i32 || neighbor_pt.x < 0 {
-                continue;
-            }
+impl SearchStats {
+    pub fn record(&mut self, result: &SearchResult) {
+        self.searches += 1;
+        self.expanded += result.expanded;
+        if let Some(path) = &result.path {
+            self.paths_found += 1;
+            self.path_steps += path.len().saturating_sub(1) as u64;
+        }
+    }
 
-            if neighbor_pt.y < 0 || neighbor_pt.y >= height as i32 {
-                continue;
-            }
+    pub fn add(&mut self, other: &SearchStats) {
+        self.searches += other.searches;
+        self.expanded += other.expanded;
+        self.path_steps += other.path_steps;
+        self.paths_found += other.paths_found;
+    }
+}
 
-            if snake_pts.get_pt(&neighbor_pt).is_some() {
-                continue;
-            }
+#[derive(Copy, Clone, Eq, PartialEq)]
+struct OpenNode {
+    f_score: u32,
+    tie: i64,
+    order: u64,
+    pt: Point,
+}
 
-            let pos_g_score =
-                current_g_score.map(|g| g + distance(&current, &neighbor_pt)).unwrap_or(f32::INFINITY);
+impl Ord for OpenNode {
+    fn cmp(&self, other: &OpenNode) -> Ordering {
+        // BinaryHeap is a max heap, so reverse to pop the lowest score first
+        Reverse((self.f_score, self.tie, self.order)).cmp(&Reverse((other.f_score, other.tie, other.order)))
+    }
+}
 
-            let g_score_neighbor = g_scores.get_pt(&neighbor_pt).cloned().unwrap_or(f32::INFINITY);
+impl PartialOrd for OpenNode {
+    fn partial_cmp(&self, other: &OpenNode) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
 
-            if pos_g_score < g_score_neighbor {
-                came_from.set_pt(&neighbor_pt, current);
-                g_scores.set_pt(&neighbor_pt, pos_g_score);
-                f_scores.set_pt(&neighbor_pt, pos_g_score + distance(&neighbor_pt, end));
+pub fn a_star_path(end: &Point, snake: &Snake, walls: &Matrix<bool>) -> Option<Vec<Point>> {
+    return a_star_search(std::slice::from_ref(end), snake, walls, &SearchConfig::default()).path;
+}
+
+/// Searches from the snake's head to the closest of `ends` around the
+/// snake's body and the walls.
+pub fn a_star_search(ends: &[Point], snake: &Snake, walls: &Matrix<bool>, config: &SearchConfig) -> SearchResult {
+    let mut blocked = walls.clone();
+
+    snake.for_each_segment(|pt, _| {
+        blocked[pt] = true;
+    });
+
+    return search(snake.get_head(), ends, &blocked, snake.topology(), config);
+}
+
+/// A* from `start` to whichever of `ends` is cheapest to reach over the
+/// 4-connected grid, avoiding every cell set in `blocked` except `start`.
+/// Each step costs 1, and the heuristic estimates the nearest end.
+pub fn search(
+    start: &Point,
+    ends: &[Point],
+    blocked: &Matrix<bool>,
+    topology: Topology,
+    config: &SearchConfig,
+) -> SearchResult {
+    let width = blocked.get_width();
+    let height = blocked.get_height();
+    let mut came_from: Matrix<Option<Point>> = Matrix::new(width, height);
+    let mut g_scores: Matrix<u32> = Matrix::filled(width, height, u32::MAX);
+    let mut closed: Matrix<bool> = Matrix::new(width, height);
+    g_scores[*start] = 0;
+
+    let estimate = |pt: &Point| {
+        ends.iter()
+            .map(|end| config.heuristic.estimate(topology.distance(pt, end, width, height)))
+            .min()
+            .unwrap_or(0)
+    };
+
+    let mut open_set = BinaryHeap::new();
+    let mut opened = 0;
+    let mut open = |open_set: &mut BinaryHeap<OpenNode>, pt: Point, g_score: u32| {
+        let tie = match config.tie_break {
+            TieBreak::Deepest => -(g_score as i64),
+            TieBreak::Shallowest => g_score as i64,
+            TieBreak::Newest => -(opened as i64),
+            TieBreak::Oldest => opened as i64,
+        };
+        open_set.push(OpenNode {
+            f_score: g_score.saturating_add(estimate(&pt)),
+            tie: tie,
+            order: opened,
+            pt: pt,
+        });
+        opened += 1;
+    };
+    open(&mut open_set, *start, 0);
+
+    let mut expanded = 0;
+    while let Some(OpenNode { pt: current, .. }) = open_set.pop() {
+        // a cell is pushed again whenever its score improves, skip the stale
+        // entries left behind
+        if closed[current] {
+            continue;
+        }
+        closed[current] = true;
+        expanded += 1;
+
+        if ends.contains(&current) {
+            return SearchResult {
+                path: Some(reconstruct_path(&current, &came_from)),
+                expanded: expanded,
+            };
+        }
+
+        let pos_g_score = g_scores[current] + 1;
+        for neighbor_pt in topology.neighbours(current, width, height) {
+            if blocked[neighbor_pt] || closed[neighbor_pt] {
+                continue;
+            }
 
-                open_set.insert(neighbor_pt);
+            if pos_g_score < g_scores[neighbor_pt] {
+                came_from[neighbor_pt] = Some(current);
+                g_scores[neighbor_pt] = pos_g_score;
+                open(&mut open_set, neighbor_pt, pos_g_score);
             }
         }
     }
 
-    return None;
+    return SearchResult {
+        path: None,
+        expanded: expanded,
+    };
 }
 
-fn reconstruct_path(current: &Point, came_from: &Matrix<Point>) -> Vec<Point> {
+fn reconstruct_path(current: &Point, came_from: &Matrix<Option<Point>>) -> Vec<Point> {
     let mut full_path = vec![*current];
 
-    let mut current: Option<&Point> = Some(current);
+    let mut current = came_from[*current];
 
-    while current.is_some() {
-        current = came_from.get_pt(current.unwrap());
-
-        if current.is_some() {
-            full_path.push(*current.unwrap());
-        }
+    while let Some(pt) = current {
+        full_path.push(pt);
+        current = came_from[pt];
     }
 
     full_path.reverse();
@@ -83,20 +252,72 @@ fn reconstruct_path(current: &Point, came_from: &Matrix<Point>) -> Vec<Point> {
     return full_path;
 }
 
-fn min_f_score(of: &HashSet<Point>, f_scores: &Matrix<f32>) -> Option<Point> {
-    let mut min: Option<(&Point, f32)> = None;
-    for node in of {
-        let node_f_score = *f_scores.get_pt(&node).expect("Node didn't have fscore?");
-        if min.is_none() || min.unwrap().1 > node_f_score {
-            min = Some((node, node_f_score));
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TIE_BREAKS: [TieBreak; 4] = [TieBreak::Deepest, TieBreak::Shallowest, TieBreak::Newest, TieBreak::Oldest];
+
+    /// A 10x8 board split by two walls, each with a gap at the opposite end,
+    /// so most paths have to wind back on themselves.
+    fn winding_board() -> Matrix<bool> {
+        let mut blocked = Matrix::new(10, 8);
+        for y in 0..7 {
+            blocked[Point::new(3, y)] = true;
+            blocked[Point::new(6, y + 1)] = true;
         }
+        blocked
     }
-    return min.map(|f| *f.0);
-}
 
-fn distance(p1: &Point, p2: &Point) -> f32 {
-    let dx = p1.x - p2.x;
-    let dy = p1.y - p2.y;
-    let dist = (((dx * dx) + (dy * dy)) as f32).sqrt();
-    return dist;
+    fn path_len(start: Point, end: Point, blocked: &Matrix<bool>, topology: Topology, config: SearchConfig) -> Option<usize> {
+        let path = search(&start, &[end], blocked, topology, &config).path?;
+        assert_eq!(path.first(), Some(&start));
+        assert_eq!(path.last(), Some(&end));
+        for pair in path.windows(2) {
+            assert_eq!(topology.distance(&pair[0], &pair[1], blocked.get_width(), blocked.get_height()), 1);
+            assert!(!blocked[pair[1]]);
+        }
+        Some(path.len())
+    }
+
+    fn assert_manhattan_matches_zero(blocked: &Matrix<bool>, topology: Topology) {
+        let open: Vec<Point> = blocked.enumerate().filter(|(_, wall)| !**wall).map(|(pt, _)| pt).collect();
+        for start in &open {
+            for end in open.iter().step_by(3) {
+                for tie_break in TIE_BREAKS.iter() {
+                    let config = |heuristic| SearchConfig {
+                        heuristic,
+                        tie_break: *tie_break,
+                    };
+                    let shortest = path_len(*start, *end, blocked, topology, config(Heuristic::Zero));
+                    let found = path_len(*start, *end, blocked, topology, config(Heuristic::Manhattan));
+                    assert_eq!(found, shortest, "{:?} to {:?} with {:?}", start, end, tie_break);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn manhattan_finds_shortest_paths() {
+        assert_manhattan_matches_zero(&winding_board(), Topology::Bounded);
+    }
+
+    #[test]
+    fn manhattan_finds_shortest_paths_on_a_torus() {
+        assert_manhattan_matches_zero(&winding_board(), Topology::Torus);
+    }
+
+    #[test]
+    fn manhattan_expands_no_more_than_zero() {
+        let blocked = winding_board();
+        let (start, end) = (Point::new(0, 0), Point::new(9, 7));
+        let expanded = |heuristic| {
+            let config = SearchConfig {
+                heuristic,
+                tie_break: TieBreak::Deepest,
+            };
+            search(&start, &[end], &blocked, Topology::Bounded, &config).expanded
+        };
+        assert!(expanded(Heuristic::Manhattan) <= expanded(Heuristic::Zero));
+    }
 }
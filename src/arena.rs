@@ -0,0 +1,473 @@
+use crate::error::GameError;
+use crate::game::{new_apple_point, start_head, EndReason, GameConfig, GameOver, SpawnPolicy};
+use crate::matrix::Matrix;
+use crate::point::Point;
+use crate::snake::Snake;
+use crate::strategy::{GameView, Strategy};
+use crate::walls::add_random_blocks;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+/// One snake in an arena and the strategy steering it.
+pub struct Entrant {
+    snake: Snake,
+    strategy: Box<dyn Strategy>,
+    score: usize,
+    over: Option<GameOver>,
+}
+
+impl Entrant {
+    pub fn snake(&self) -> &Snake {
+        &self.snake
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.strategy.name()
+    }
+
+    /// Apples eaten.
+    pub fn score(&self) -> usize {
+        self.score
+    }
+
+    /// How the snake's game ended, `None` while it is still moving.
+    pub fn game_over(&self) -> Option<GameOver> {
+        self.over
+    }
+
+    pub fn is_alive(&self) -> bool {
+        self.over.is_none()
+    }
+
+    /// Strategy and score, and how the snake died if it has.
+    pub fn status(&self) -> String {
+        let status = format!("{}={}", self.name(), self.score);
+        match self.over {
+            Some(over) if over.reason != EndReason::Won && over.reason != EndReason::Stalled => {
+                format!("{} ({:?})", status, over.reason)
+            }
+            _ => status,
+        }
+    }
+}
+
+/// Several snakes, each steered by its own strategy, sharing one board and
+/// one supply of apples. Every snake moves at once each step. A head that
+/// lands on another snake kills its owner, so two heads meeting kill both.
+/// The match ends when at most one snake is left moving, and a dead snake is
+/// cleared off the board.
+///
+/// Each strategy sees the other snakes as walls, so none of them needs to
+/// know it is in an arena.
+pub struct Arena {
+    entrants: Vec<Entrant>,
+    apples: Vec<Point>,
+    spawn: SpawnPolicy,
+
+    board_width: usize,
+    board_height: usize,
+    walls: Matrix<bool>,
+
+    seed: u64,
+    rng: StdRng,
+
+    steps: u64,
+    last_apple_step: u64,
+    stall_limit: u64,
+    over: bool,
+
+    pub running: bool,
+    pub tick_speed: u32,
+}
+
+/// Where each of `count` snakes starts: the rows are spread evenly down the
+/// board, with every head as far right as a lone snake's.
+fn start_heads(board_width: usize, board_height: usize, snake_length: usize, count: usize) -> Vec<Point> {
+    let x = start_head(board_width, board_height, snake_length).x;
+    (0..count)
+        .map(|i| Point::new(x, ((i + 1) * board_height / (count + 1)) as i32))
+        .collect()
+}
+
+impl Arena {
+    /// An arena with one snake per strategy, laid out from `config` the way
+    /// a `Game` would be.
+    pub fn new(config: &GameConfig, strategies: Vec<Box<dyn Strategy>>) -> Result<Arena, GameError> {
+        let board_width = config.board_width;
+        let board_height = config.board_height;
+
+        if board_width < 2 || board_height < 2 {
+            return Err(GameError::invalid_board(board_width, board_height, "board is too small"));
+        }
+
+        if config.snake_length == 0 {
+            return Err(GameError::invalid_board(board_width, board_height, "snake must be at least 1 long"));
+        }
+
+        if config.apple_count == 0 {
+            return Err(GameError::invalid_board(board_width, board_height, "there must be at least 1 apple"));
+        }
+
+        if strategies.is_empty() {
+            return Err(GameError::invalid_board(board_width, board_height, "there must be at least 1 snake"));
+        }
+
+        if strategies.len() >= board_height {
+            return Err(GameError::invalid_board(
+                board_width,
+                board_height,
+                &format!("no room for {} snakes", strategies.len()),
+            ));
+        }
+
+        let heads = start_heads(board_width, board_height, config.snake_length, strategies.len());
+        if heads[0].x >= board_width as i32 {
+            return Err(GameError::invalid_board(board_width, board_height, "snake does not fit on the board"));
+        }
+
+        let mut walls = match &config.walls {
+            Some(walls) if walls.get_width() != board_width || walls.get_height() != board_height => {
+                return Err(GameError::invalid_board(
+                    board_width,
+                    board_height,
+                    &format!("map is {}x{}", walls.get_width(), walls.get_height()),
+                ));
+            }
+            Some(walls) => walls.clone(),
+            None => Matrix::new(board_width, board_height),
+        };
+
+        let mut entrants = Vec::with_capacity(strategies.len());
+        let mut snake_cells = vec![];
+        for (head, strategy) in heads.into_iter().zip(strategies) {
+            let snake = Snake::new(head, config.snake_length, board_width, board_height, config.topology);
+            snake.for_each_segment(|pt, _| snake_cells.push(pt));
+            entrants.push(Entrant {
+                snake,
+                strategy,
+                score: 0,
+                over: None,
+            });
+        }
+        if snake_cells.iter().any(|pt| walls[*pt]) {
+            return Err(GameError::invalid_board(board_width, board_height, "snake starts on a wall"));
+        }
+
+        let mut rng = StdRng::seed_from_u64(config.seed);
+        if config.obstacles > 0 {
+            add_random_blocks(&mut walls, config.obstacles, &snake_cells, &mut rng);
+        }
+        let open_cells = walls.cells().filter(|wall| !**wall).count();
+
+        let mut arena = Arena {
+            entrants,
+            apples: Vec::with_capacity(config.apple_count),
+            spawn: config.spawn,
+
+            board_width,
+            board_height,
+            walls,
+
+            seed: config.seed,
+            rng,
+
+            steps: 0,
+            last_apple_step: 0,
+            stall_limit: config
+                .stall_limit
+                .unwrap_or(2 * open_cells as u64),
+            over: false,
+
+            running: false,
+            tick_speed: config.tick_speed,
+        };
+
+        for _ in 0..config.apple_count {
+            let apple = arena
+                .new_apple_point()
+                .ok_or_else(|| GameError::invalid_board(board_width, board_height, "no room for the apples"))?;
+            arena.apples.push(apple);
+        }
+
+        // cycle strategies lay their cycle around the walls alone, as the
+        // other snakes will have moved off their cells soon enough
+        for entrant in &mut arena.entrants {
+            let view = GameView {
+                snake: &entrant.snake,
+                apples: &arena.apples,
+                walls: &arena.walls,
+                board_width,
+                board_height,
+                topology: entrant.snake.topology(),
+            };
+            entrant.strategy.on_reset(&view, &mut arena.rng)?;
+        }
+        Ok(arena)
+    }
+
+    /// The walls with every other live snake than `idx` added, which is how
+    /// that snake's strategy sees the board.
+    fn walls_for(&self, idx: usize) -> Matrix<bool> {
+        let mut walls = self.walls.clone();
+        for (i, entrant) in self.entrants.iter().enumerate() {
+            if i != idx && entrant.is_alive() {
+                entrant.snake.for_each_segment(|pt, _| {
+                    if let Some(wall) = walls.get_mut(&pt) {
+                        *wall = true;
+                    }
+                });
+            }
+        }
+        walls
+    }
+
+    fn knock_out(&mut self, idx: usize, reason: EndReason) {
+        let entrant = &mut self.entrants[idx];
+        entrant.over = Some(GameOver {
+            reason,
+            steps: self.steps,
+            length: entrant.snake.size(),
+        });
+    }
+
+    /// Ends the match for every snake still moving.
+    fn finish(&mut self, reason: EndReason) {
+        for idx in 0..self.entrants.len() {
+            if self.entrants[idx].is_alive() {
+                self.knock_out(idx, reason);
+            }
+        }
+        self.over = true;
+        self.running = false;
+    }
+
+    /// Why the head of snake `idx` can't stay where it is, if it can't.
+    fn collision(&self, idx: usize) -> Option<EndReason> {
+        let snake = &self.entrants[idx].snake;
+        let head = *snake.get_head();
+        if head.x < 0 || head.y < 0 || head.x >= self.board_width as i32 || head.y >= self.board_height as i32 {
+            return Some(EndReason::HitWall);
+        }
+
+        if self.walls[head] {
+            return Some(EndReason::HitWall);
+        }
+
+        if snake.check_collide() {
+            return Some(EndReason::HitSelf);
+        }
+
+        let hit_other = self
+            .entrants
+            .iter()
+            .enumerate()
+            .any(|(i, other)| i != idx && other.is_alive() && other.snake.on_snake(head));
+        if hit_other {
+            return Some(EndReason::HitSnake);
+        }
+        None
+    }
+
+    /// Asks every live snake's strategy for a move, moves them all at once
+    /// and then applies the rules. A snake whose strategy can't reach an
+    /// apple or fails in any other way drops out, and the rest play on.
+    pub fn step(&mut self) -> Result<(), GameError> {
+        if self.over {
+            return Ok(());
+        }
+
+        let mut moves = Vec::with_capacity(self.entrants.len());
+        let mut failures = vec![];
+        for idx in 0..self.entrants.len() {
+            if !self.entrants[idx].is_alive() {
+                moves.push(None);
+                continue;
+            }
+
+            let walls = self.walls_for(idx);
+            let entrant = &mut self.entrants[idx];
+            let view = GameView {
+                snake: &entrant.snake,
+                apples: &self.apples,
+                walls: &walls,
+                board_width: self.board_width,
+                board_height: self.board_height,
+                topology: entrant.snake.topology(),
+            };
+            match entrant.strategy.next_direction(&view) {
+                Ok(dir) => moves.push(Some(dir)),
+                Err(err) => {
+                    moves.push(None);
+                    let reason = match err {
+                        GameError::UnreachableApple => EndReason::NoPath,
+                        _ => EndReason::StrategyFailed,
+                    };
+                    failures.push((idx, reason));
+                }
+            }
+        }
+
+        for (idx, reason) in failures {
+            self.knock_out(idx, reason);
+        }
+
+        self.steps += 1;
+        for (entrant, dir) in self.entrants.iter_mut().zip(&moves) {
+            if let Some(dir) = dir {
+                entrant.snake.move_dir(dir);
+            }
+        }
+
+        // every collision is found before any snake is cleared away, so two
+        // heads meeting take each other out
+        let crashes: Vec<(usize, EndReason)> = (0..self.entrants.len())
+            .filter(|idx| self.entrants[*idx].is_alive())
+            .filter_map(|idx| self.collision(idx).map(|reason| (idx, reason)))
+            .collect();
+        for (idx, reason) in crashes {
+            self.knock_out(idx, reason);
+        }
+
+        for idx in 0..self.entrants.len() {
+            if !self.entrants[idx].is_alive() {
+                continue;
+            }
+            let head = *self.entrants[idx].snake.get_head();
+            let eaten = match self.apples.iter().position(|apple| *apple == head) {
+                Some(eaten) => eaten,
+                None => continue,
+            };
+
+            self.last_apple_step = self.steps;
+            self.entrants[idx].score += 1;
+            self.entrants[idx].snake.expand();
+            self.apples[eaten] = match self.new_apple_point() {
+                Some(apple) => apple,
+                None => {
+                    self.finish(EndReason::Won);
+                    return Ok(());
+                }
+            };
+
+            let walls = self.walls_for(idx);
+            let entrant = &mut self.entrants[idx];
+            let view = GameView {
+                snake: &entrant.snake,
+                apples: &self.apples,
+                walls: &walls,
+                board_width: self.board_width,
+                board_height: self.board_height,
+                topology: entrant.snake.topology(),
+            };
+            entrant.strategy.on_apple_eaten(&view);
+        }
+
+        let alive = self.entrants.iter().filter(|entrant| entrant.is_alive()).count();
+        if alive == 0 || (alive == 1 && self.entrants.len() > 1) {
+            self.finish(EndReason::Won);
+        } else if self.steps - self.last_apple_step >= self.stall_limit {
+            self.finish(EndReason::Stalled);
+        }
+        Ok(())
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.over
+    }
+
+    /// The snake that lasted longest, with more apples breaking a tie, or
+    /// `None` while the match is on or if it was a draw.
+    pub fn winner(&self) -> Option<usize> {
+        if !self.over {
+            return None;
+        }
+
+        // a snake still moving at the end outlasts one that died that step
+        let rank = |entrant: &Entrant| match entrant.over {
+            Some(over) => (over.steps, matches!(over.reason, EndReason::Won | EndReason::Stalled), entrant.score),
+            None => (self.steps, true, entrant.score),
+        };
+        let best = self.entrants.iter().map(rank).max()?;
+        let mut leaders = self.entrants.iter().enumerate().filter(|(_, entrant)| rank(entrant) == best);
+        let (winner, _) = leaders.next()?;
+        if leaders.next().is_some() {
+            return None;
+        }
+        Some(winner)
+    }
+
+    /// The HUD without the snakes: the board, the step and, once the match
+    /// is over, who won.
+    pub fn header(&self) -> String {
+        let header = format!(
+            "Seed={}, Board={}x{}, Speed={}, Step={}",
+            self.seed, self.board_width, self.board_height, self.tick_speed, self.steps
+        );
+
+        if !self.over {
+            return header;
+        }
+        match self.winner() {
+            Some(winner) => format!("{}, Winner={}", header, winner + 1),
+            None => format!("{}, Draw", header),
+        }
+    }
+
+    pub fn status_line(&self) -> String {
+        let mut status = self.header();
+        for (i, entrant) in self.entrants.iter().enumerate() {
+            status += &format!(", {}:{}", i + 1, entrant.status());
+        }
+        status
+    }
+
+    pub fn entrants(&self) -> &[Entrant] {
+        &self.entrants
+    }
+
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn apples(&self) -> &[Point] {
+        &self.apples
+    }
+
+    pub fn walls(&self) -> &Matrix<bool> {
+        &self.walls
+    }
+
+    pub fn board_width(&self) -> usize {
+        self.board_width
+    }
+
+    pub fn board_height(&self) -> usize {
+        self.board_height
+    }
+
+    /// Where the next apple goes, off the walls, every live snake and the
+    /// other apples.
+    fn new_apple_point(&mut self) -> Option<Point> {
+        let live: Vec<&Snake> = self
+            .entrants
+            .iter()
+            .filter(|entrant| entrant.is_alive())
+            .map(|entrant| &entrant.snake)
+            .collect();
+        let heads: Vec<Point> = live.iter().map(|snake| *snake.get_head()).collect();
+        let walls = &self.walls;
+        let apples = &self.apples;
+        new_apple_point(
+            self.spawn,
+            self.board_width,
+            self.board_height,
+            &heads,
+            |pt| !walls[pt] && !live.iter().any(|snake| snake.on_snake(pt)) && !apples.contains(&pt),
+            &mut self.rng,
+        )
+    }
+}
@@ -0,0 +1,435 @@
+use crate::a_star::SearchStats;
+use crate::arena::Arena;
+use crate::error::GameError;
+use crate::game::{EndReason, Game, GameConfig, SpawnPolicy, TickOutcome};
+use crate::hamiltonian_matrix::CycleGenerator;
+use crate::strategy::{self, StrategyOptions};
+use crate::topology::Topology;
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+pub struct BenchConfig {
+    pub strategy: String,
+    pub options: StrategyOptions,
+    pub games: usize,
+    pub threads: usize,
+    /// Game `i` of the run is played with `game.seed + i`.
+    pub game: GameConfig,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum GameEnd {
+    Over(EndReason),
+    StrategyFailed,
+}
+
+impl GameEnd {
+    fn label(&self) -> String {
+        match self {
+            GameEnd::Over(reason) => format!("{:?}", reason),
+            GameEnd::StrategyFailed => "StrategyFailed".to_string(),
+        }
+    }
+}
+
+pub struct GameResult {
+    pub seed: u64,
+    pub end: GameEnd,
+    pub steps: u64,
+    pub length: usize,
+    pub apple_steps: Vec<u64>,
+    /// Totals over the game's path searches, for strategies that search.
+    pub search: Option<SearchStats>,
+}
+
+pub struct BenchReport {
+    pub strategy: String,
+    pub cycle: CycleGenerator,
+    pub width: usize,
+    pub height: usize,
+    pub seed: u64,
+    pub apple_count: usize,
+    pub spawn: SpawnPolicy,
+    /// Wall cells on the map, if one was given.
+    pub map_walls: usize,
+    pub obstacles: usize,
+    pub topology: Topology,
+    pub results: Vec<GameResult>,
+}
+
+pub fn play_game(
+    strategy: &str,
+    options: &StrategyOptions,
+    config: &GameConfig,
+) -> Result<GameResult, GameError> {
+    let strategy = strategy::build(strategy, options)?;
+    let mut game = Game::new(config, strategy)?;
+
+    let mut apple_steps = vec![];
+    let mut last_apple_step = 0;
+
+    let end = loop {
+        match game.step() {
+            Ok(TickOutcome::Continue) => {}
+            Ok(TickOutcome::AteApple) => {
+                apple_steps.push(game.steps() - last_apple_step);
+                last_apple_step = game.steps();
+            }
+            Ok(TickOutcome::Over(over)) => {
+                if over.reason == EndReason::Won {
+                    apple_steps.push(game.steps() - last_apple_step);
+                }
+                break GameEnd::Over(over.reason);
+            }
+            Err(_) => break GameEnd::StrategyFailed,
+        }
+    };
+
+    Ok(GameResult {
+        seed: config.seed,
+        end,
+        steps: game.steps(),
+        length: game.snake().size(),
+        apple_steps,
+        search: game.search_stats(),
+    })
+}
+
+/// Plays games `0..games` spread over `threads` workers and returns their
+/// results in order, stopping early once one of them fails.
+pub(crate) fn play_all<T, F>(games: usize, threads: usize, play: F) -> Result<Vec<T>, GameError>
+where
+    T: Send,
+    F: Fn(usize) -> Result<T, GameError> + Sync,
+{
+    let next_game = AtomicUsize::new(0);
+    let results = Mutex::new(Vec::with_capacity(games));
+
+    crossbeam::scope(|scope| {
+        for _ in 0..threads.max(1) {
+            scope.spawn(|_| loop {
+                let game_idx = next_game.fetch_add(1, Ordering::SeqCst);
+                if game_idx >= games {
+                    break;
+                }
+
+                let result = play(game_idx);
+                let failed = result.is_err();
+                results.lock().unwrap().push((game_idx, result));
+                if failed {
+                    next_game.store(games, Ordering::SeqCst);
+                }
+            });
+        }
+    })
+    .expect("Bench worker panicked");
+
+    let mut results = results.into_inner().unwrap();
+    results.sort_by_key(|(idx, _)| *idx);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+/// Plays every game of the bench, stopping early if a game can't be set up.
+pub fn run_bench(config: &BenchConfig) -> Result<BenchReport, GameError> {
+    let results = play_all(config.games, config.threads, |game_idx| {
+        let mut game_config = config.game.clone();
+        game_config.seed = config.game.seed.wrapping_add(game_idx as u64);
+        play_game(&config.strategy, &config.options, &game_config)
+    })?;
+
+    Ok(BenchReport {
+        strategy: config.strategy.clone(),
+        cycle: config.options.cycle,
+        width: config.game.board_width,
+        height: config.game.board_height,
+        seed: config.game.seed,
+        apple_count: config.game.apple_count,
+        spawn: config.game.spawn,
+        map_walls: map_walls(&config.game),
+        obstacles: config.game.obstacles,
+        topology: config.game.topology,
+        results,
+    })
+}
+
+fn map_walls(config: &GameConfig) -> usize {
+    config.walls.as_ref().map_or(0, |walls| walls.cells().filter(|wall| **wall).count())
+}
+
+/// Board size, followed by whatever about the board isn't the default.
+fn board_label(width: usize, height: usize, topology: Topology, map_walls: usize, obstacles: usize) -> String {
+    let mut board = format!("{}x{}", width, height);
+    if topology != Topology::Bounded {
+        board += &format!(", Topology={}", topology.name());
+    }
+    if map_walls > 0 {
+        board += &format!(", MapWalls={}", map_walls);
+    }
+    if obstacles > 0 {
+        board += &format!(", Obstacles={}", obstacles);
+    }
+    board
+}
+
+fn mean(vals: &[u64]) -> f64 {
+    if vals.is_empty() {
+        return 0.0;
+    }
+    vals.iter().sum::<u64>() as f64 / vals.len() as f64
+}
+
+fn median(vals: &mut [u64]) -> f64 {
+    if vals.is_empty() {
+        return 0.0;
+    }
+    vals.sort_unstable();
+    let mid = vals.len() / 2;
+    if vals.len() % 2 == 0 {
+        return (vals[mid - 1] + vals[mid]) as f64 / 2.0;
+    }
+    vals[mid] as f64
+}
+
+impl BenchReport {
+    /// Strategy and cycle layout, and the topology if it isn't the default.
+    pub fn label(&self) -> String {
+        let label = format!("{}/{}", self.strategy, self.cycle.name());
+        if self.topology == Topology::Bounded {
+            return label;
+        }
+        format!("{}/{}", label, self.topology.name())
+    }
+
+    pub fn mean_steps_per_apple(&self) -> f64 {
+        let apple_steps: Vec<u64> = self
+            .results
+            .iter()
+            .flat_map(|r| r.apple_steps.iter().cloned())
+            .collect();
+        mean(&apple_steps)
+    }
+
+    pub fn print(&self) {
+        let games = self.results.len();
+        let won = self.results.iter().filter(|r| r.end == GameEnd::Over(EndReason::Won)).count();
+        let total_steps: u64 = self.results.iter().map(|r| r.steps).sum();
+        let mut apple_steps: Vec<u64> = self
+            .results
+            .iter()
+            .flat_map(|r| r.apple_steps.iter().cloned())
+            .collect();
+        let lengths: Vec<u64> = self.results.iter().map(|r| r.length as u64).collect();
+
+        let mut ends: HashMap<GameEnd, usize> = HashMap::new();
+        for result in &self.results {
+            *ends.entry(result.end).or_insert(0) += 1;
+        }
+        let mut ends: Vec<(GameEnd, usize)> = ends.into_iter().collect();
+        ends.sort();
+
+        println!(
+            "Strategy={}, Cycle={}, Board={}, Apples={}/{}, Games={}, Seed={}",
+            self.strategy,
+            self.cycle.name(),
+            board_label(self.width, self.height, self.topology, self.map_walls, self.obstacles),
+            self.apple_count,
+            self.spawn.name(),
+            games,
+            self.seed
+        );
+        println!(
+            "  Completion rate:      {:.2}% ({}/{})",
+            100.0 * won as f64 / games.max(1) as f64,
+            won,
+            games
+        );
+        println!("  Total steps:          {}", total_steps);
+        println!("  Mean steps per apple: {:.2}", self.mean_steps_per_apple());
+        println!("  Median steps/apple:   {:.1}", median(&mut apple_steps));
+        println!("  Mean final length:    {:.1}", mean(&lengths));
+        let mut search = SearchStats::default();
+        for stats in self.results.iter().filter_map(|r| r.search.as_ref()) {
+            search.add(stats);
+        }
+        if search.searches > 0 {
+            println!("  Path searches:        {}", search.searches);
+            println!(
+                "  Expanded per search:  {:.1}",
+                search.expanded as f64 / search.searches as f64
+            );
+            println!(
+                "  Mean path length:     {:.2}",
+                search.path_steps as f64 / search.paths_found.max(1) as f64
+            );
+        }
+        for (end, count) in ends {
+            println!("  {:<21} {}", format!("{}:", end.label()), count);
+        }
+
+        let lost_seeds: Vec<String> = self
+            .results
+            .iter()
+            .filter(|r| r.end != GameEnd::Over(EndReason::Won))
+            .take(10)
+            .map(|r| r.seed.to_string())
+            .collect();
+        if !lost_seeds.is_empty() {
+            println!("  Lost game seeds:      {}", lost_seeds.join(", "));
+        }
+    }
+}
+
+/// Prints each report's mean steps per apple against the first one's.
+pub fn print_comparison(reports: &[BenchReport]) {
+    let base = match reports.first() {
+        Some(base) => base,
+        None => return,
+    };
+    let base_steps = base.mean_steps_per_apple();
+
+    println!("Steps per apple against {}:", base.label());
+    for report in reports {
+        let steps = report.mean_steps_per_apple();
+        let change = if base_steps > 0.0 {
+            100.0 * (steps - base_steps) / base_steps
+        } else {
+            0.0
+        };
+        println!(
+            "  {:<32} {:>8.2} ({:+.1}%)",
+            format!("{}:", report.label()),
+            steps,
+            change
+        );
+    }
+}
+
+pub struct ArenaBenchConfig {
+    /// One snake per strategy, in this order on every board.
+    pub strategies: Vec<String>,
+    pub options: StrategyOptions,
+    pub games: usize,
+    pub threads: usize,
+    /// Match `i` of the run is played with `game.seed + i`.
+    pub game: GameConfig,
+}
+
+pub struct ArenaResult {
+    pub seed: u64,
+    pub steps: u64,
+    pub winner: Option<usize>,
+    pub scores: Vec<usize>,
+    /// How each snake's game ended.
+    pub ends: Vec<EndReason>,
+}
+
+pub struct ArenaReport {
+    pub strategies: Vec<String>,
+    pub cycle: CycleGenerator,
+    pub width: usize,
+    pub height: usize,
+    pub seed: u64,
+    pub apple_count: usize,
+    pub spawn: SpawnPolicy,
+    pub map_walls: usize,
+    pub obstacles: usize,
+    pub topology: Topology,
+    pub results: Vec<ArenaResult>,
+}
+
+pub fn play_arena(
+    strategies: &[String],
+    options: &StrategyOptions,
+    config: &GameConfig,
+) -> Result<ArenaResult, GameError> {
+    let strategies = strategies
+        .iter()
+        .map(|name| strategy::build(name, options))
+        .collect::<Result<Vec<_>, GameError>>()?;
+    let mut arena = Arena::new(config, strategies)?;
+    while !arena.is_over() {
+        arena.step()?;
+    }
+
+    Ok(ArenaResult {
+        seed: config.seed,
+        steps: arena.steps(),
+        winner: arena.winner(),
+        scores: arena.entrants().iter().map(|entrant| entrant.score()).collect(),
+        ends: arena
+            .entrants()
+            .iter()
+            .filter_map(|entrant| entrant.game_over())
+            .map(|over| over.reason)
+            .collect(),
+    })
+}
+
+/// Plays every match of the arena bench, stopping early if one fails.
+pub fn run_arena_bench(config: &ArenaBenchConfig) -> Result<ArenaReport, GameError> {
+    let results = play_all(config.games, config.threads, |game_idx| {
+        let mut game_config = config.game.clone();
+        game_config.seed = config.game.seed.wrapping_add(game_idx as u64);
+        play_arena(&config.strategies, &config.options, &game_config)
+    })?;
+
+    Ok(ArenaReport {
+        strategies: config.strategies.clone(),
+        cycle: config.options.cycle,
+        width: config.game.board_width,
+        height: config.game.board_height,
+        seed: config.game.seed,
+        apple_count: config.game.apple_count,
+        spawn: config.game.spawn,
+        map_walls: map_walls(&config.game),
+        obstacles: config.game.obstacles,
+        topology: config.game.topology,
+        results,
+    })
+}
+
+impl ArenaReport {
+    pub fn print(&self) {
+        let games = self.results.len();
+        let draws = self.results.iter().filter(|r| r.winner.is_none()).count();
+        let steps: Vec<u64> = self.results.iter().map(|r| r.steps).collect();
+
+        println!(
+            "Arena={}, Cycle={}, Board={}, Apples={}/{}, Games={}, Seed={}",
+            self.strategies.join(","),
+            self.cycle.name(),
+            board_label(self.width, self.height, self.topology, self.map_walls, self.obstacles),
+            self.apple_count,
+            self.spawn.name(),
+            games,
+            self.seed
+        );
+        println!("  Mean match steps:     {:.1}", mean(&steps));
+        println!("  Draws:                {}", draws);
+
+        for (i, strategy) in self.strategies.iter().enumerate() {
+            let wins = self.results.iter().filter(|r| r.winner == Some(i)).count();
+            let scores: Vec<u64> = self.results.iter().map(|r| r.scores[i] as u64).collect();
+            let mut ends: HashMap<EndReason, usize> = HashMap::new();
+            for result in &self.results {
+                *ends.entry(result.ends[i]).or_insert(0) += 1;
+            }
+            let mut ends: Vec<(EndReason, usize)> = ends.into_iter().collect();
+            ends.sort();
+
+            println!("  {}:{}", i + 1, strategy);
+            println!(
+                "    Win rate:           {:.2}% ({}/{})",
+                100.0 * wins as f64 / games.max(1) as f64,
+                wins,
+                games
+            );
+            println!("    Mean score:         {:.2}", mean(&scores));
+            for (end, count) in ends {
+                println!("    {:<19} {}", format!("{:?}:", end), count);
+            }
+        }
+    }
+}
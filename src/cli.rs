@@ -0,0 +1,527 @@
+use rust_snake::bench::{ArenaBenchConfig, BenchConfig};
+use rust_snake::encode::{Encoding, ENCODING_NAMES};
+use rust_snake::env::ActionSpace;
+use rust_snake::evolve::EvolveConfig;
+use rust_snake::strategy::{self, StrategyOptions};
+use rust_snake::walls::parse_map;
+use rust_snake::{
+    CycleGenerator, GameConfig, Heuristic, Network, SpawnPolicy, TieBreak, Topology, CYCLE_NAMES, TOPOLOGY_NAMES,
+};
+
+use std::fs;
+use std::thread;
+
+/// Milliseconds between ticks when a person is steering and no speed was
+/// given, slow enough to react to.
+const HUMAN_TICK_SPEED: u32 = 150;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputMode {
+    Tui,
+    Headless,
+}
+
+pub struct PlayOptions {
+    pub strategy: String,
+    pub strategy_options: StrategyOptions,
+    pub game: GameConfig,
+    /// Set when the board size was given explicitly rather than left to fit
+    /// the terminal.
+    pub width: Option<usize>,
+    pub height: Option<usize>,
+    pub paused: bool,
+    pub output: OutputMode,
+}
+
+/// An arena played live, with one snake per strategy.
+pub struct ArenaOptions {
+    pub strategies: Vec<String>,
+    /// Everything but the strategy, which is taken from `strategies`.
+    pub play: PlayOptions,
+}
+
+pub struct TrainOptions {
+    pub evolve: EvolveConfig,
+    /// Where the fittest network is saved after every generation.
+    pub out: String,
+}
+
+pub struct CheckOptions {
+    pub width: usize,
+    pub height: usize,
+    pub cycles: Vec<CycleGenerator>,
+    /// Cycle `i` of each layout is generated with `seed + i`.
+    pub seed: u64,
+    pub count: u64,
+}
+
+pub enum Command {
+    Play(PlayOptions),
+    /// One bench per strategy, all over the same seeds.
+    Bench(Vec<BenchConfig>),
+    Arena(ArenaOptions),
+    /// Arena matches played headless and tallied.
+    ArenaBench(ArenaBenchConfig),
+    Check(CheckOptions),
+    Train(TrainOptions),
+    Help,
+}
+
+pub fn usage() -> String {
+    format!(
+        "Usage:
+  rust_snake [play] [OPTIONS]
+  rust_snake bench [OPTIONS] [--games N] [--threads N]
+  rust_snake arena [OPTIONS] [--games N] [--threads N]
+  rust_snake check [--size WxH] [--cycle A,B] [--seed N] [--count N]
+  rust_snake train [OPTIONS] [--out FILE]
+  rust_snake help
+
+Options:
+  --strategy NAME   pathing strategy, one of {} (default hamilton)
+  --mode NAME       same as --strategy
+  --cycle NAME      hamiltonian cycle layout, one of {} (default prim)
+  --heuristic NAME  A* heuristic, manhattan, zero or weighted:N (default manhattan)
+  --tie-break NAME  A* order for equal scores, deepest, shallowest, newest or
+                    oldest (default deepest)
+  --genome FILE     network for the neural strategy, saved by train
+  --width N         board width (default: fit the terminal, or 40 headless)
+  --height N        board height (default: fit the terminal, or 20 headless)
+  --size WxH        board width and height together
+  --speed MS        milliseconds between ticks (default 20, or 150 with a
+                    human playing)
+  --seed N          seed for the cycle and apple sequence (default random)
+  --length N        starting snake length (default 5)
+  --map FILE        walls read from a file of # and ., which also sets the
+                    board size
+  --obstacles N     random 2x2 blocks of wall to add (default 0)
+  --topology NAME   board edges, one of {} (default bounded)
+  --apples N        apples on the board at once (default 1)
+  --spawn NAME      where apples appear, uniform or far from the head
+                    (default uniform)
+  --paused          start the game paused
+  --output MODE     tui or headless (default tui)
+
+Bench options:
+  --strategy A,B    compare several strategies over the same games
+  --cycle A,B       compare several cycle layouts over the same games
+  --topology A,B    compare several board topologies over the same games
+  --games N         number of games to play (default 100)
+  --threads N       worker threads (default one per core)
+
+Arena options:
+  --strategy A,B    one snake per strategy, which may repeat
+                    (default astar,safe-astar)
+  --games N         play N matches headless and tally the wins rather than
+                    watching one
+  --threads N       worker threads for --games (default one per core)
+
+Check options:
+  --count N         cycles of each layout to generate and validate (default 100)
+
+Train options, which also take the board options above:
+  --population N    networks in each generation (default 100)
+  --generations N   generations to breed (default 50)
+  --elite N         fittest networks kept as they are (default 4)
+  --hidden A,B      hidden layer widths (default 16)
+  --encoding NAME   what the network sees, one of {} (default compact)
+  --actions NAME    absolute or relative moves (default relative)
+  --crossover P     chance a child mixes two parents (default 0.7)
+  --mutation-rate P chance each weight is nudged (default 0.1)
+  --mutation-scale X
+                    largest nudge to a weight (default 0.5)
+  --games N         games each network plays per generation (default 4)
+  --threads N       worker threads (default one per core)
+  --out FILE        where the fittest network is saved (default snake.genome)",
+        strategy::STRATEGY_NAMES.join("|"),
+        CYCLE_NAMES.join("|"),
+        TOPOLOGY_NAMES.join("|"),
+        ENCODING_NAMES.join("|")
+    )
+}
+
+fn parse_size(size: &str) -> Option<(usize, usize)> {
+    let mut parts = size.split('x');
+    let width = parts.next()?.parse().ok()?;
+    let height = parts.next()?.parse().ok()?;
+    if parts.next().is_some() {
+        return None;
+    }
+    Some((width, height))
+}
+
+fn parse_num<T: std::str::FromStr>(flag: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("Bad value for {}: {}", flag, value))
+}
+
+/// A chance, which must be between 0 and 1.
+fn parse_rate(flag: &str, value: &str) -> Result<f64, String> {
+    let rate: f64 = parse_num(flag, value)?;
+    if !(0.0..=1.0).contains(&rate) {
+        return Err(format!("{} must be between 0 and 1, not {}", flag, value));
+    }
+    Ok(rate)
+}
+
+fn parse_cycle(name: &str) -> Result<CycleGenerator, String> {
+    CycleGenerator::from_name(name).ok_or_else(|| format!("Unexpected cycle layout {}", name))
+}
+
+fn parse_topology(name: &str) -> Result<Topology, String> {
+    Topology::from_name(name).ok_or_else(|| format!("Unexpected topology {}", name))
+}
+
+fn check_strategy(name: &str) -> Result<String, String> {
+    if !strategy::STRATEGY_NAMES.contains(&name) {
+        return Err(format!("Unexpected Pathing Mode {}", name));
+    }
+    Ok(name.to_string())
+}
+
+pub fn check_game(config: &GameConfig) -> Result<(), String> {
+    let width = config.board_width;
+    let height = config.board_height;
+    if width < 4 || height < 4 {
+        return Err(format!("Board {}x{} is too small", width, height));
+    }
+    if width % 2 != 0 && height % 2 != 0 {
+        return Err(format!("Board {}x{} must have at least one even dimension", width, height));
+    }
+    if config.snake_length >= width {
+        return Err(format!(
+            "Snake of length {} does not fit on a board {} wide",
+            config.snake_length, width
+        ));
+    }
+    let walls = config.walls.as_ref().map_or(0, |walls| walls.cells().filter(|wall| **wall).count());
+    if config.apple_count + config.snake_length + 1 + walls > width * height {
+        return Err(format!("No room for {} apples on a {}x{} board", config.apple_count, width, height));
+    }
+    Ok(())
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Play,
+    Bench,
+    Arena,
+    Check,
+    Train,
+}
+
+fn takes_value(flag: &str, mode: Mode) -> bool {
+    match flag {
+        "--width" | "--height" | "--size" | "--seed" => true,
+        "--cycle" => mode != Mode::Train,
+        "--length" | "--apples" | "--spawn" | "--map" | "--obstacles" | "--topology" => mode != Mode::Check,
+        "--strategy" | "--mode" | "--speed" | "--heuristic" | "--tie-break" | "--genome" => {
+            mode != Mode::Check && mode != Mode::Train
+        }
+        "--output" => mode == Mode::Play || mode == Mode::Arena,
+        "--games" | "--threads" => mode == Mode::Bench || mode == Mode::Arena || mode == Mode::Train,
+        "--count" => mode == Mode::Check,
+        "--population" | "--generations" | "--elite" | "--hidden" | "--encoding" | "--actions" | "--crossover"
+        | "--mutation-rate" | "--mutation-scale" | "--out" => mode == Mode::Train,
+        _ => false,
+    }
+}
+
+pub fn parse_args(args: &[String]) -> Result<Command, String> {
+    let (mode, rest) = match args.first().map(|s| s.as_str()) {
+        Some("help") | Some("--help") | Some("-h") => return Ok(Command::Help),
+        Some("bench") => (Mode::Bench, &args[1..]),
+        Some("arena") => (Mode::Arena, &args[1..]),
+        Some("check") => (Mode::Check, &args[1..]),
+        Some("train") => (Mode::Train, &args[1..]),
+        Some("play") => (Mode::Play, &args[1..]),
+        _ => (Mode::Play, args),
+    };
+    let is_bench = mode == Mode::Bench;
+    let takes_list = is_bench || mode == Mode::Arena;
+
+    let mut play = PlayOptions {
+        strategy: "hamilton".to_string(),
+        strategy_options: StrategyOptions::default(),
+        game: GameConfig {
+            seed: rand::random(),
+            ..GameConfig::default()
+        },
+        width: None,
+        height: None,
+        paused: false,
+        output: OutputMode::Tui,
+    };
+    let mut strategies = vec![];
+    let mut cycles = vec![];
+    let mut topologies = vec![];
+    let mut games = None;
+    let mut count = 100;
+    let mut map_size = None;
+    let mut speed_given = false;
+    let mut evolve = EvolveConfig::default();
+    let mut out = "snake.genome".to_string();
+    let mut threads = thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1);
+
+    let mut rest = rest.iter();
+    while let Some(flag) = rest.next() {
+        let flag = flag.as_str();
+        if flag == "--paused" && (mode == Mode::Play || mode == Mode::Arena) {
+            play.paused = true;
+            continue;
+        }
+
+        if !flag.starts_with("--") && mode == Mode::Play {
+            // bare strategy name, as in `rust_snake astar`
+            play.strategy = check_strategy(flag)?;
+            continue;
+        }
+
+        if !takes_value(flag, mode) {
+            return Err(format!("Unexpected argument {}", flag));
+        }
+
+        let value = rest
+            .next()
+            .ok_or_else(|| format!("Missing value for {}", flag))?;
+        match flag {
+            "--strategy" | "--mode" if takes_list => {
+                strategies = value
+                    .split(',')
+                    .map(check_strategy)
+                    .collect::<Result<Vec<String>, String>>()?;
+            }
+            "--strategy" | "--mode" => play.strategy = check_strategy(value)?,
+            "--cycle" if is_bench || mode == Mode::Check => {
+                cycles = value
+                    .split(',')
+                    .map(parse_cycle)
+                    .collect::<Result<Vec<CycleGenerator>, String>>()?;
+            }
+            "--cycle" => play.strategy_options.cycle = parse_cycle(value)?,
+            "--heuristic" => {
+                play.strategy_options.search.heuristic = Heuristic::from_name(value)
+                    .ok_or_else(|| format!("Unexpected heuristic {}", value))?;
+            }
+            "--tie-break" => {
+                play.strategy_options.search.tie_break = TieBreak::from_name(value)
+                    .ok_or_else(|| format!("Unexpected tie break {}", value))?;
+            }
+            "--width" => play.width = Some(parse_num(flag, value)?),
+            "--height" => play.height = Some(parse_num(flag, value)?),
+            "--size" => {
+                let (width, height) =
+                    parse_size(value).ok_or_else(|| format!("Bad board size {}", value))?;
+                play.width = Some(width);
+                play.height = Some(height);
+            }
+            "--speed" => {
+                play.game.tick_speed = parse_num(flag, value)?;
+                speed_given = true;
+            }
+            "--seed" => play.game.seed = parse_num(flag, value)?,
+            "--length" => play.game.snake_length = parse_num(flag, value)?,
+            "--apples" => play.game.apple_count = parse_num(flag, value)?,
+            "--map" => {
+                let text = fs::read_to_string(value).map_err(|err| format!("Couldn't read map {}: {}", value, err))?;
+                let walls = parse_map(&text).map_err(|err| format!("{}: {}", value, err))?;
+                map_size = Some((walls.get_width(), walls.get_height()));
+                play.game.walls = Some(walls);
+            }
+            "--obstacles" => play.game.obstacles = parse_num(flag, value)?,
+            "--topology" if is_bench => {
+                topologies = value
+                    .split(',')
+                    .map(parse_topology)
+                    .collect::<Result<Vec<Topology>, String>>()?;
+            }
+            "--topology" => play.game.topology = parse_topology(value)?,
+            "--spawn" => {
+                play.game.spawn = SpawnPolicy::from_name(value)
+                    .ok_or_else(|| format!("Unexpected spawn policy {}", value))?;
+            }
+            "--output" => {
+                play.output = match value.as_ref() {
+                    "tui" => OutputMode::Tui,
+                    "headless" => OutputMode::Headless,
+                    _ => return Err(format!("Unexpected output mode {}", value)),
+                }
+            }
+            "--games" => games = Some(parse_num(flag, value)?),
+            "--threads" => threads = parse_num(flag, value)?,
+            "--count" => count = parse_num(flag, value)?,
+            "--genome" => {
+                let text = fs::read_to_string(value).map_err(|err| format!("Couldn't read genome {}: {}", value, err))?;
+                let network = Network::parse(&text).map_err(|err| format!("{}: {}", value, err))?;
+                play.strategy_options.network = Some(network);
+            }
+            "--population" => evolve.population = parse_num(flag, value)?,
+            "--generations" => evolve.generations = parse_num(flag, value)?,
+            "--elite" => evolve.elite = parse_num(flag, value)?,
+            "--hidden" => {
+                evolve.hidden = value
+                    .split(',')
+                    .map(|width| parse_num(flag, width))
+                    .collect::<Result<Vec<usize>, String>>()?;
+            }
+            "--encoding" => {
+                evolve.encoding = Encoding::from_name(value).ok_or_else(|| format!("Unexpected encoding {}", value))?;
+            }
+            "--actions" => {
+                evolve.actions =
+                    ActionSpace::from_name(value).ok_or_else(|| format!("Unexpected action space {}", value))?;
+            }
+            "--crossover" => evolve.crossover_rate = parse_rate(flag, value)?,
+            "--mutation-rate" => evolve.mutation_rate = parse_rate(flag, value)?,
+            "--mutation-scale" => {
+                evolve.mutation_scale = parse_num(flag, value)?;
+                if !evolve.mutation_scale.is_finite() || evolve.mutation_scale <= 0.0 {
+                    return Err(format!("{} must be above 0, not {}", flag, value));
+                }
+            }
+            "--out" => out = value.clone(),
+            _ => return Err(format!("Unexpected argument {}", flag)),
+        }
+    }
+
+    if play.game.snake_length == 0 {
+        return Err("Snake length must be at least 1".to_string());
+    }
+    if play.game.apple_count == 0 {
+        return Err("There must be at least 1 apple".to_string());
+    }
+
+    if let Some((width, height)) = map_size {
+        if play.width.is_some_and(|w| w != width) || play.height.is_some_and(|h| h != height) {
+            return Err(format!("Board size doesn't match the {}x{} map", width, height));
+        }
+        play.width = Some(width);
+        play.height = Some(height);
+    }
+
+    play.game.board_width = play.width.unwrap_or(play.game.board_width);
+    play.game.board_height = play.height.unwrap_or(play.game.board_height);
+
+    if mode == Mode::Check {
+        if cycles.is_empty() {
+            cycles = CYCLE_NAMES.iter().filter_map(|name| CycleGenerator::from_name(name)).collect();
+        }
+        return Ok(Command::Check(CheckOptions {
+            width: play.game.board_width,
+            height: play.game.board_height,
+            cycles,
+            seed: play.game.seed,
+            count,
+        }));
+    }
+
+    let human = match mode {
+        Mode::Play => play.strategy == "human",
+        _ => strategies.iter().any(|name| name == "human"),
+    };
+    if human && (is_bench || games.is_some() || play.output == OutputMode::Headless) {
+        return Err("The human strategy can only be played in the tui".to_string());
+    }
+    if human && !speed_given {
+        play.game.tick_speed = HUMAN_TICK_SPEED;
+    }
+
+    let neural = match mode {
+        Mode::Play => play.strategy == "neural",
+        _ => strategies.iter().any(|name| name == "neural"),
+    };
+    if neural && play.strategy_options.network.is_none() {
+        return Err("The neural strategy needs a network, from --genome FILE".to_string());
+    }
+
+    if mode == Mode::Train {
+        check_game(&play.game)?;
+        if evolve.population < 2 {
+            return Err("The population must be at least 2".to_string());
+        }
+        if evolve.hidden.contains(&0) {
+            return Err("Hidden layers must be at least 1 wide".to_string());
+        }
+        evolve.game = play.game;
+        evolve.threads = threads;
+        if let Some(games) = games {
+            evolve.games = games;
+        }
+        return Ok(Command::Train(TrainOptions {
+            evolve,
+            out,
+        }));
+    }
+
+    // the terminal size isn't known until ncurses is up, so a tui board
+    // that fits the terminal is checked later
+    let fits_terminal = play.width.is_none() || play.height.is_none();
+    if is_bench || games.is_some() || play.output == OutputMode::Headless || !fits_terminal {
+        check_game(&play.game)?;
+    }
+
+    if mode == Mode::Arena {
+        if strategies.is_empty() {
+            strategies = vec!["astar".to_string(), "safe-astar".to_string()];
+        }
+        if strategies.len() >= play.game.board_height {
+            return Err(format!(
+                "No room for {} snakes on a board {} high",
+                strategies.len(),
+                play.game.board_height
+            ));
+        }
+        return match games {
+            Some(games) => Ok(Command::ArenaBench(ArenaBenchConfig {
+                strategies,
+                options: play.strategy_options.clone(),
+                games,
+                threads,
+                game: play.game,
+            })),
+            None => Ok(Command::Arena(ArenaOptions {
+                strategies,
+                play,
+            })),
+        };
+    }
+
+    if is_bench {
+        if strategies.is_empty() {
+            strategies.push(play.strategy);
+        }
+        if cycles.is_empty() {
+            cycles.push(play.strategy_options.cycle);
+        }
+        if topologies.is_empty() {
+            topologies.push(play.game.topology);
+        }
+
+        let mut configs = vec![];
+        for strategy in &strategies {
+            for cycle in &cycles {
+                for topology in &topologies {
+                    configs.push(BenchConfig {
+                        strategy: strategy.clone(),
+                        options: StrategyOptions {
+                            cycle: *cycle,
+                            ..play.strategy_options.clone()
+                        },
+                        games: games.unwrap_or(100),
+                        threads,
+                        game: GameConfig {
+                            topology: *topology,
+                            ..play.game.clone()
+                        },
+                    });
+                }
+            }
+        }
+        return Ok(Command::Bench(configs));
+    }
+
+    Ok(Command::Play(play))
+}
@@ -24,6 +24,21 @@ impl Direction {
         }
     }
 
+    /// The direction a quarter turn anticlockwise.
+    pub fn left(&self) -> Direction {
+        match self {
+            Direction::UP => Direction::LEFT,
+            Direction::RIGHT => Direction::UP,
+            Direction::DOWN => Direction::RIGHT,
+            Direction::LEFT => Direction::DOWN,
+        }
+    }
+
+    /// The direction a quarter turn clockwise.
+    pub fn right(&self) -> Direction {
+        self.left().oposite()
+    }
+
     pub fn all() -> &'static [Direction] {
         &ALL_DIRS
     }
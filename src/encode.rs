@@ -0,0 +1,222 @@
+//! Fixed length encodings of what a strategy sees, as flat `f32` vectors
+//! for learning agents. Each function documents its layout; the board's
+//! edges count as walls unless the board is a torus.
+
+use crate::direction::Direction;
+use crate::point::Point;
+use crate::strategy::GameView;
+use crate::topology::Topology;
+
+/// Which encoding an environment hands its agent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Encoding {
+    Grid,
+    Rays,
+    #[default]
+    Compact,
+}
+
+pub const ENCODING_NAMES: [&str; 3] = ["grid", "rays", "compact"];
+
+impl Encoding {
+    pub fn from_name(name: &str) -> Option<Encoding> {
+        match name {
+            "grid" => Some(Encoding::Grid),
+            "rays" => Some(Encoding::Rays),
+            "compact" => Some(Encoding::Compact),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Encoding::Grid => "grid",
+            Encoding::Rays => "rays",
+            Encoding::Compact => "compact",
+        }
+    }
+
+    /// Length of the encoding on a `width` by `height` board.
+    pub fn size(&self, width: usize, height: usize) -> usize {
+        match self {
+            Encoding::Grid => GRID_PLANES * width * height,
+            Encoding::Rays => RAY_DIRS.len() * RAY_FEATURES,
+            Encoding::Compact => COMPACT_SIZE,
+        }
+    }
+
+    pub fn encode(&self, view: &GameView) -> Vec<f32> {
+        match self {
+            Encoding::Grid => grid_planes(view),
+            Encoding::Rays => rays(view),
+            Encoding::Compact => compact(view),
+        }
+    }
+}
+
+/// Planes in `grid_planes`: body, head, apples and walls.
+pub const GRID_PLANES: usize = 4;
+
+/// Values per ray in `rays`: wall, body and apple.
+pub const RAY_FEATURES: usize = 3;
+
+/// Steps `rays` looks along: up, up right, right, down right, down, down
+/// left, left and up left.
+pub const RAY_DIRS: [(i32, i32); 8] = [(0, -1), (1, -1), (1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0), (-1, -1)];
+
+/// Length of `compact`.
+pub const COMPACT_SIZE: usize = 11;
+
+/// One `width * height` plane per channel, in the order body, head, apples,
+/// walls. Cell `(x, y)` of plane `c` is at `c * width * height + y * width +
+/// x` and is 1 where the channel has something, 0 elsewhere. The body plane
+/// leaves out the head, and a head that has left the board is left out too.
+pub fn grid_planes(view: &GameView) -> Vec<f32> {
+    let plane = view.board_width * view.board_height;
+    let mut grid = vec![0.0; GRID_PLANES * plane];
+    let mut mark = |channel: usize, pt: Point| {
+        if view.walls.get(&pt).is_some() {
+            grid[channel * plane + pt.y as usize * view.board_width + pt.x as usize] = 1.0;
+        }
+    };
+
+    view.snake.for_each_segment(|pt, i| {
+        let channel = if i == 0 { 1 } else { 0 };
+        mark(channel, pt);
+    });
+    for apple in view.apples {
+        mark(2, *apple);
+    }
+    for (pt, wall) in view.walls.enumerate() {
+        if *wall {
+            mark(3, pt);
+        }
+    }
+    grid
+}
+
+/// The cell `dx`, `dy` from `pt`, wrapped on a torus, or `None` off the
+/// edge of a bounded board.
+fn offset(view: &GameView, pt: Point, dx: i32, dy: i32) -> Option<Point> {
+    let next = pt.add(dx, dy);
+    if view.topology == Topology::Torus {
+        return Some(Point::new(
+            next.x.rem_euclid(view.board_width as i32),
+            next.y.rem_euclid(view.board_height as i32),
+        ));
+    }
+    if next.x < 0 || next.y < 0 || next.x >= view.board_width as i32 || next.y >= view.board_height as i32 {
+        return None;
+    }
+    Some(next)
+}
+
+/// What the head sees looking out in each of `RAY_DIRS`, `RAY_FEATURES`
+/// values per ray: one over the steps to the first wall, to the first body
+/// segment and to the first apple along it, or 0 where the ray meets none
+/// before a wall stops it. Rays on a torus wrap and stop short of coming
+/// back round to the head.
+pub fn rays(view: &GameView) -> Vec<f32> {
+    let head = *view.snake.get_head();
+    let reach = view.board_width.max(view.board_height);
+
+    let mut features = Vec::with_capacity(RAY_DIRS.len() * RAY_FEATURES);
+    for (dx, dy) in RAY_DIRS.iter() {
+        let mut seen = [0.0; RAY_FEATURES];
+        let mut pt = head;
+        for dist in 1..=reach {
+            let near = 1.0 / dist as f32;
+            pt = match offset(view, pt, *dx, *dy) {
+                Some(pt) if pt != head => pt,
+                Some(_) => break,
+                None => {
+                    seen[0] = near;
+                    break;
+                }
+            };
+            if view.walls[pt] {
+                seen[0] = near;
+                break;
+            }
+            if seen[1] == 0.0 && view.snake.on_snake(pt) {
+                seen[1] = near;
+            }
+            if seen[2] == 0.0 && view.apples.contains(&pt) {
+                seen[2] = near;
+            }
+        }
+        features.extend_from_slice(&seen);
+    }
+    features
+}
+
+/// True if moving the head onto `pt` would end the game. The tail is safe
+/// unless the snake is growing, as it moves off in the same tick.
+fn deadly(view: &GameView, pt: Option<Point>) -> bool {
+    let pt = match pt {
+        Some(pt) => pt,
+        None => return true,
+    };
+    if view.walls[pt] {
+        return true;
+    }
+    view.snake.on_snake(pt) && (pt != view.snake.get_tail() || view.snake.is_growing())
+}
+
+/// Signed steps from `from` to `to` along one axis of `size` cells, going
+/// the short way round on a torus.
+fn axis_offset(from: i32, to: i32, size: usize, topology: Topology) -> i32 {
+    let delta = to - from;
+    if topology == Topology::Torus && delta.unsigned_abs() as usize * 2 > size {
+        return delta - delta.signum() * size as i32;
+    }
+    delta
+}
+
+fn flag(set: bool) -> f32 {
+    if set {
+        1.0
+    } else {
+        0.0
+    }
+}
+
+/// `COMPACT_SIZE` flags, each 1 or 0, relative to the snake's heading where
+/// that matters:
+///
+/// * 0 to 2: danger straight on, to the left and to the right
+/// * 3 to 6: heading up, right, down, left
+/// * 7 to 10: the nearest apple is up, right, down, left of the head
+pub fn compact(view: &GameView) -> Vec<f32> {
+    let head = *view.snake.get_head();
+    let heading = view.snake.heading();
+    let danger = |dir: Direction| {
+        let delta = Point::new(0, 0).dir_adj(&dir);
+        flag(deadly(view, offset(view, head, delta.x, delta.y)))
+    };
+
+    let mut features = Vec::with_capacity(COMPACT_SIZE);
+    features.push(danger(heading));
+    features.push(danger(heading.left()));
+    features.push(danger(heading.right()));
+    for dir in Direction::all() {
+        features.push(flag(*dir == heading));
+    }
+
+    let apple = view.apples.iter().min_by_key(|apple| {
+        view.topology
+            .distance(&head, apple, view.board_width, view.board_height)
+    });
+    let (dx, dy) = match apple {
+        Some(apple) => (
+            axis_offset(head.x, apple.x, view.board_width, view.topology),
+            axis_offset(head.y, apple.y, view.board_height, view.topology),
+        ),
+        None => (0, 0),
+    };
+    features.push(flag(dy < 0));
+    features.push(flag(dx > 0));
+    features.push(flag(dy > 0));
+    features.push(flag(dx < 0));
+    features
+}
@@ -0,0 +1,188 @@
+use crate::direction::Direction;
+use crate::encode::Encoding;
+use crate::error::GameError;
+use crate::game::{EndReason, Game, GameConfig, GameSnapshot, TickOutcome};
+use crate::strategy::{HumanStrategy, TurnQueue};
+
+/// How an action number maps to a move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ActionSpace {
+    /// 0 to 3 move up, right, down and left. An action back into the neck
+    /// carries on straight instead, as it would for a player.
+    #[default]
+    Absolute,
+    /// 0 carries on straight, 1 turns left and 2 turns right.
+    Relative,
+}
+
+impl ActionSpace {
+    pub fn from_name(name: &str) -> Option<ActionSpace> {
+        match name {
+            "absolute" => Some(ActionSpace::Absolute),
+            "relative" => Some(ActionSpace::Relative),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ActionSpace::Absolute => "absolute",
+            ActionSpace::Relative => "relative",
+        }
+    }
+
+    /// Number of actions, numbered from 0.
+    pub fn size(&self) -> usize {
+        match self {
+            ActionSpace::Absolute => 4,
+            ActionSpace::Relative => 3,
+        }
+    }
+
+    /// The move `action` makes for a snake heading in `heading`, or `None`
+    /// if there is no such action.
+    pub fn direction(&self, action: usize, heading: Direction) -> Option<Direction> {
+        match self {
+            ActionSpace::Absolute => Direction::all().get(action).copied(),
+            ActionSpace::Relative => match action {
+                0 => Some(heading),
+                1 => Some(heading.left()),
+                2 => Some(heading.right()),
+                _ => None,
+            },
+        }
+    }
+}
+
+/// What each step is worth to the agent.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rewards {
+    /// For eating an apple, including the one that wins the game.
+    pub apple: f32,
+    /// For the move that ends the game any way but winning.
+    pub death: f32,
+    /// Added to every move, usually a small penalty so dawdling costs.
+    pub step: f32,
+}
+
+impl Default for Rewards {
+    fn default() -> Rewards {
+        Rewards {
+            apple: 1.0,
+            death: -1.0,
+            step: -0.01,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct EnvConfig {
+    /// The board to play on. Its seed is the one `Env::new` starts with.
+    pub game: GameConfig,
+    pub rewards: Rewards,
+    pub actions: ActionSpace,
+    /// How the board is encoded into `Observation::features`.
+    pub encoding: Encoding,
+}
+
+/// What the agent sees after a reset or a step.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Observation {
+    pub snapshot: GameSnapshot,
+    /// The board in the environment's encoding, `Env::observation_size` long.
+    pub features: Vec<f32>,
+}
+
+/// Details of a step beyond its reward.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StepInfo {
+    pub outcome: TickOutcome,
+    pub steps: u64,
+    pub length: usize,
+}
+
+/// A game steered by an agent one action at a time, in the style of a gym
+/// environment. The snake is moved through the same turn queue a player's
+/// keys feed, so an agent plays exactly the game the tui shows.
+pub struct Env {
+    config: EnvConfig,
+    turns: TurnQueue,
+    game: Game,
+}
+
+impl Env {
+    pub fn new(config: EnvConfig) -> Result<Env, GameError> {
+        let turns = TurnQueue::new();
+        let game = Game::new(&config.game, Box::new(HumanStrategy::new(turns.clone())))?;
+        Ok(Env {
+            config,
+            turns,
+            game,
+        })
+    }
+
+    /// Starts a new game with `seed` and returns what it looks like.
+    pub fn reset(&mut self, seed: u64) -> Result<Observation, GameError> {
+        self.config.game.seed = seed;
+        self.game = Game::new(&self.config.game, Box::new(HumanStrategy::new(self.turns.clone())))?;
+        Ok(self.observe())
+    }
+
+    /// Moves the snake by `action` and returns what the board looks like
+    /// after, the reward for the move, whether the game is over, and the
+    /// details. Stepping a finished game changes nothing and earns nothing.
+    pub fn step(&mut self, action: usize) -> Result<(Observation, f32, bool, StepInfo), GameError> {
+        let actions = self.config.actions;
+        let dir = actions
+            .direction(action, self.game.snake().heading())
+            .ok_or(GameError::InvalidAction {
+                action,
+                actions: actions.size(),
+            })?;
+
+        let done_before = self.game.game_over().is_some();
+        if !done_before {
+            self.turns.push(dir);
+        }
+        let outcome = self.game.step()?;
+
+        let rewards = self.config.rewards;
+        let reward = match outcome {
+            _ if done_before => 0.0,
+            TickOutcome::Continue => rewards.step,
+            TickOutcome::AteApple => rewards.step + rewards.apple,
+            TickOutcome::Over(over) if over.reason == EndReason::Won => rewards.step + rewards.apple,
+            TickOutcome::Over(_) => rewards.step + rewards.death,
+        };
+        let info = StepInfo {
+            outcome,
+            steps: self.game.steps(),
+            length: self.game.snake().size(),
+        };
+        let done = matches!(outcome, TickOutcome::Over(_));
+        Ok((self.observe(), reward, done, info))
+    }
+
+    pub fn observe(&self) -> Observation {
+        Observation {
+            snapshot: self.game.snapshot(),
+            features: self.config.encoding.encode(&self.game.view()),
+        }
+    }
+
+    /// Length of every observation's features.
+    pub fn observation_size(&self) -> usize {
+        self.config
+            .encoding
+            .size(self.game.board_width(), self.game.board_height())
+    }
+
+    pub fn action_space(&self) -> ActionSpace {
+        self.config.actions
+    }
+
+    /// The game being played, for drawing it or reading more of its state.
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+}
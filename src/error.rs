@@ -0,0 +1,93 @@
+use crate::hamiltonian_matrix::CycleProblem;
+use crate::point::Point;
+use std::fmt;
+
+/// Everything that can go wrong setting up or stepping a game.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GameError {
+    /// The board can't be played with the requested settings.
+    InvalidBoard {
+        width: usize,
+        height: usize,
+        reason: String,
+    },
+    /// No strategy is registered under the given name.
+    UnknownStrategy(String),
+    /// No path exists from the snake's head to the apple.
+    UnreachableApple,
+    /// A map couldn't be read.
+    InvalidMap(String),
+    /// A saved neural network couldn't be read.
+    InvalidGenome(String),
+    /// A generated hamiltonian cycle failed validation.
+    InvalidCycle(Vec<CycleProblem>),
+    /// A wall that doesn't fill a whole 2x2 block of the maze, so no cycle
+    /// can be laid around it.
+    MisalignedWall(Point),
+    /// An environment was given an action outside its action space.
+    InvalidAction {
+        action: usize,
+        actions: usize,
+    },
+    /// A strategy couldn't decide on a move.
+    StrategyFailed {
+        strategy: &'static str,
+        reason: String,
+    },
+}
+
+impl GameError {
+    pub fn invalid_board(width: usize, height: usize, reason: &str) -> GameError {
+        GameError::InvalidBoard {
+            width,
+            height,
+            reason: reason.to_string(),
+        }
+    }
+
+    pub fn strategy_failed(strategy: &'static str, reason: &str) -> GameError {
+        GameError::StrategyFailed {
+            strategy,
+            reason: reason.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for GameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GameError::InvalidBoard {
+                width,
+                height,
+                reason,
+            } => write!(f, "Invalid {}x{} board: {}", width, height, reason),
+            GameError::UnknownStrategy(name) => write!(f, "Unknown strategy {}", name),
+            GameError::UnreachableApple => write!(f, "No path to the apple"),
+            GameError::InvalidMap(reason) => write!(f, "Invalid map: {}", reason),
+            GameError::InvalidGenome(reason) => write!(f, "Invalid genome: {}", reason),
+            GameError::InvalidCycle(problems) => match problems.first() {
+                Some(first) => write!(
+                    f,
+                    "Invalid hamiltonian cycle with {} problems, first {}",
+                    problems.len(),
+                    first
+                ),
+                None => write!(f, "Invalid hamiltonian cycle"),
+            },
+            GameError::MisalignedWall(pt) => write!(
+                f,
+                "Wall at ({}, {}) can't be gone around: cycles only go around walls that fill whole 2x2 blocks \
+                 starting on even coordinates, clear of an odd last column or row",
+                pt.x, pt.y
+            ),
+            GameError::InvalidAction { action, actions } => {
+                write!(f, "Action {} is out of range, there are {} actions", action, actions)
+            }
+            GameError::StrategyFailed { strategy, reason } => {
+                write!(f, "Strategy {} failed: {}", strategy, reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for GameError {}
@@ -0,0 +1,204 @@
+//! Trains `Network`s for the neural strategy with a genetic algorithm. Each
+//! generation every network plays the same few headless games, the fittest
+//! few are carried over unchanged, and the rest of the next generation is
+//! bred from parents picked by tournament, mixed by uniform crossover and
+//! then mutated.
+
+use crate::bench::play_all;
+use crate::encode::Encoding;
+use crate::env::ActionSpace;
+use crate::error::GameError;
+use crate::game::{EndReason, Game, GameConfig, TickOutcome};
+use crate::neural::Network;
+use crate::strategy::NeuralStrategy;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use std::cmp::Ordering;
+
+#[derive(Debug, Clone)]
+pub struct EvolveConfig {
+    pub population: usize,
+    pub generations: usize,
+    /// Fittest networks copied unchanged into the next generation.
+    pub elite: usize,
+    /// Networks drawn at random to pick each parent, the fittest winning.
+    pub tournament: usize,
+    /// Chance a child mixes two parents rather than copying one.
+    pub crossover_rate: f64,
+    /// Chance each of a child's weights is nudged.
+    pub mutation_rate: f64,
+    /// Largest nudge a mutation makes either way. Weights aren't mutated at
+    /// all unless it's above 0.
+    pub mutation_scale: f32,
+    /// Widths of the hidden layers.
+    pub hidden: Vec<usize>,
+    pub encoding: Encoding,
+    pub actions: ActionSpace,
+    /// Games each network plays per generation.
+    pub games: usize,
+    pub threads: usize,
+    /// The board to train on. Its seed also seeds the weights and breeding,
+    /// and generation `g` plays games seeded from `seed + g * games`.
+    pub game: GameConfig,
+}
+
+impl Default for EvolveConfig {
+    fn default() -> EvolveConfig {
+        EvolveConfig {
+            population: 100,
+            generations: 50,
+            elite: 4,
+            tournament: 3,
+            crossover_rate: 0.7,
+            mutation_rate: 0.1,
+            mutation_scale: 0.5,
+            hidden: vec![16],
+            encoding: Encoding::Compact,
+            actions: ActionSpace::Relative,
+            games: 4,
+            threads: 1,
+            game: GameConfig::default(),
+        }
+    }
+}
+
+/// How one generation went.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Generation {
+    /// Counting from 1.
+    pub generation: usize,
+    pub best_fitness: f64,
+    pub mean_fitness: f64,
+    /// Mean apples the fittest network ate per game.
+    pub best_apples: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Fitness {
+    score: f64,
+    apples: f64,
+}
+
+/// How well `network` plays `games` games seeded from `first_seed`: the
+/// mean over them of the apples eaten plus a thousandth per step survived.
+/// Survival counts a little so that networks which can't eat yet still learn
+/// to stay alive, but steps in a game that stalls count for nothing.
+fn evaluate(network: &Network, config: &GameConfig, first_seed: u64, games: usize) -> Result<Fitness, GameError> {
+    let mut score = 0.0;
+    let mut apples = 0.0;
+    for i in 0..games {
+        let mut game_config = config.clone();
+        game_config.seed = first_seed.wrapping_add(i as u64);
+        let mut game = Game::new(&game_config, Box::new(NeuralStrategy::new(network.clone())))?;
+        let over = loop {
+            if let TickOutcome::Over(over) = game.step()? {
+                break over;
+            }
+        };
+
+        let eaten = over.length.saturating_sub(config.snake_length) as f64;
+        apples += eaten;
+        score += eaten;
+        if over.reason != EndReason::Stalled {
+            score += over.steps as f64 / 1000.0;
+        }
+    }
+    Ok(Fitness {
+        score: score / games as f64,
+        apples: apples / games as f64,
+    })
+}
+
+/// Index of the fittest of `size` networks drawn at random.
+fn tournament(fitness: &[Fitness], size: usize, rng: &mut StdRng) -> usize {
+    let mut best = rng.gen_range(0, fitness.len());
+    for _ in 1..size {
+        let pick = rng.gen_range(0, fitness.len());
+        if fitness[pick].score > fitness[best].score {
+            best = pick;
+        }
+    }
+    best
+}
+
+/// Weights of a child of `a` and `b`, mixed and mutated as `config` says.
+fn breed(a: &Network, b: &Network, config: &EvolveConfig, rng: &mut StdRng) -> Vec<f32> {
+    let mix = rng.gen::<f64>() < config.crossover_rate;
+    a
+        .weights()
+        .iter()
+        .zip(b.weights())
+        .map(|(wa, wb)| {
+            let mut weight = if mix && rng.gen::<bool>() { *wb } else { *wa };
+            if config.mutation_scale > 0.0 && rng.gen::<f64>() < config.mutation_rate {
+                weight += rng.gen_range(-config.mutation_scale, config.mutation_scale);
+            }
+            weight
+        })
+        .collect()
+}
+
+/// Runs every generation, calling `on_generation` after each with how it
+/// went and the fittest network seen so far, and returns that network.
+pub fn evolve<F: FnMut(&Generation, &Network)>(config: &EvolveConfig, mut on_generation: F) -> Result<Network, GameError> {
+    let mut rng = StdRng::seed_from_u64(config.game.seed);
+    let inputs = config
+        .encoding
+        .size(config.game.board_width, config.game.board_height);
+    let population_size = config.population.max(2);
+    let mut population = (0..population_size)
+        .map(|_| Network::random(inputs, &config.hidden, config.encoding, config.actions, &mut rng))
+        .collect::<Result<Vec<Network>, GameError>>()?;
+    let mut best: Option<(Network, f64)> = None;
+
+    for generation in 0..config.generations {
+        let first_seed = config
+            .game
+            .seed
+            .wrapping_add((generation * config.games) as u64);
+        let fitness = play_all(population.len(), config.threads, |idx| {
+            evaluate(&population[idx], &config.game, first_seed, config.games.max(1))
+        })?;
+
+        let mut ranked: Vec<usize> = (0..population.len()).collect();
+        ranked.sort_by(|a, b| {
+            fitness[*b]
+                .score
+                .partial_cmp(&fitness[*a].score)
+                .unwrap_or(Ordering::Equal)
+        });
+        let fittest = ranked[0];
+        if best.as_ref().map_or(true, |(_, score)| fitness[fittest].score > *score) {
+            best = Some((population[fittest].clone(), fitness[fittest].score));
+        }
+
+        let report = Generation {
+            generation: generation + 1,
+            best_fitness: fitness[fittest].score,
+            mean_fitness: fitness.iter().map(|f| f.score).sum::<f64>() / fitness.len() as f64,
+            best_apples: fitness[fittest].apples,
+        };
+        if let Some((network, _)) = &best {
+            on_generation(&report, network);
+        }
+
+        let mut next: Vec<Network> = ranked
+            .iter()
+            .take(config.elite.min(population_size))
+            .map(|idx| population[*idx].clone())
+            .collect();
+        while next.len() < population_size {
+            let a = &population[tournament(&fitness, config.tournament, &mut rng)];
+            let b = &population[tournament(&fitness, config.tournament, &mut rng)];
+            let weights = breed(a, b, config, &mut rng);
+            next.push(a.with_weights(weights));
+        }
+        population = next;
+    }
+
+    match best {
+        Some((network, _)) => Ok(network),
+        None => Ok(population.swap_remove(0)),
+    }
+}
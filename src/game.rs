@@ -1,345 +1,538 @@
-use crate::a_star::a_star_path;
-use crate::direction::Direction;
-use crate::hamiltonian_matrix::HamiltonMatrix;
+#![allow(clippy::needless_return, clippy::redundant_field_names)]
+
+use crate::a_star::SearchStats;
+use crate::error::GameError;
+use crate::matrix::Matrix;
 use crate::point::Point;
 use crate::snake::Snake;
+use crate::strategy::{GameView, Strategy};
+use crate::topology::Topology;
+use crate::walls::add_random_blocks;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+#[derive(Debug, Clone)]
+pub struct GameConfig {
+    pub board_width: usize,
+    pub board_height: usize,
+    pub seed: u64,
+    pub snake_length: usize,
+    pub tick_speed: u32,
+    /// Steps allowed between apples before the game counts as stalled.
+    /// Defaults to two laps of the board.
+    pub stall_limit: Option<u64>,
+    /// Apples on the board at once.
+    pub apple_count: usize,
+    pub spawn: SpawnPolicy,
+    /// Walls inside the board, the same size as it.
+    pub walls: Option<Matrix<bool>>,
+    /// Random 2x2 blocks of wall added to the board.
+    pub obstacles: usize,
+    pub topology: Topology,
+}
+
+impl Default for GameConfig {
+    fn default() -> GameConfig {
+        GameConfig {
+            board_width: 40,
+            board_height: 20,
+            seed: 0,
+            snake_length: 5,
+            tick_speed: 20,
+            stall_limit: None,
+            apple_count: 1,
+            spawn: SpawnPolicy::Uniform,
+            walls: None,
+            obstacles: 0,
+            topology: Topology::Bounded,
+        }
+    }
+}
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-pub enum PathMode {
-    ASTAR,
-    HAMILTON,
+/// Where new apples are placed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpawnPolicy {
+    /// Any free cell, each equally likely.
+    Uniform,
+    /// The free cell furthest from the head out of a few picked uniformly.
+    FarFromHead,
+}
+
+impl SpawnPolicy {
+    pub fn from_name(name: &str) -> Option<SpawnPolicy> {
+        match name {
+            "uniform" => Some(SpawnPolicy::Uniform),
+            "far" => Some(SpawnPolicy::FarFromHead),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SpawnPolicy::Uniform => "uniform",
+            SpawnPolicy::FarFromHead => "far",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum EndReason {
+    /// The snake filled the board, or all of it the apples left free.
+    Won,
+    HitSelf,
+    /// The head left the board or ran into a wall.
+    HitWall,
+    /// The head ran into another snake in an arena, head first or into its
+    /// body.
+    HitSnake,
+    /// The strategy found no way to reach the apple.
+    NoPath,
+    /// The strategy failed for any other reason, such as a cycle strategy
+    /// whose next cell was taken by another snake in an arena.
+    StrategyFailed,
+    /// Too many steps passed without eating.
+    Stalled,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GameOver {
+    pub reason: EndReason,
+    pub steps: u64,
+    pub length: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TickOutcome {
+    Continue,
+    AteApple,
+    Over(GameOver),
+}
+
+/// Owned copy of the visible state of a game, for callers that want to keep
+/// it around while the game moves on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameSnapshot {
+    pub board_width: usize,
+    pub board_height: usize,
+    /// Every cell of the snake, head first.
+    pub snake: Vec<Point>,
+    pub apples: Vec<Point>,
+    pub walls: Vec<Point>,
+    pub steps: u64,
+    pub over: Option<GameOver>,
 }
 
 pub struct Game {
     snake: Snake,
-    apple: Point,
-    ham_cycle: HamiltonMatrix,
-    a_star_path: Vec<Point>,
-    a_star_current: usize,
-    min_x: i32,
-    min_y: i32,
-    max_x: i32,
-    max_y: i32,
+    apples: Vec<Point>,
+    spawn: SpawnPolicy,
+    strategy: Box<dyn Strategy>,
 
     board_width: usize,
     board_height: usize,
+    walls: Matrix<bool>,
+    /// Cells that aren't walls.
+    open_cells: usize,
+
+    seed: u64,
+    rng: StdRng,
+
+    steps: u64,
+    last_apple_step: u64,
+    stall_limit: u64,
+    over: Option<GameOver>,
 
     pub running: bool,
     pub tick_speed: u32,
-    current_path_mode: PathMode,
 }
 
 const SNAKE_HEAD: Point = Point { x: 10, y: 10 };
 
+/// Random cells tried for an apple before listing the free ones.
+const APPLE_GUESSES: usize = 16;
+/// Free cells `SpawnPolicy::FarFromHead` picks between.
+const FAR_CANDIDATES: usize = 4;
+
+/// Where the head starts. The body trails off to the left, so the head is
+/// pushed right far enough to fit it on the board.
+pub(crate) fn start_head(board_width: usize, board_height: usize, snake_length: usize) -> Point {
+    let x = (snake_length as i32).max(SNAKE_HEAD.x.min(board_width as i32 / 2));
+    let y = SNAKE_HEAD.y.min(board_height as i32 / 2);
+    return Point::new(x, y);
+}
+
 impl Game {
-    pub fn new(min_x: i32, min_y: i32, max_x: i32, max_y: i32, path_mode: PathMode) -> Game {
-        let board_width = (max_x - min_x) as usize;
-        let board_height = (max_y - min_y) as usize;
+    pub fn new(config: &GameConfig, strategy: Box<dyn Strategy>) -> Result<Game, GameError> {
+        let board_width = config.board_width;
+        let board_height = config.board_height;
 
-        if board_height % 2 != 0 {
-            panic!("Bad board height");
+        if board_width < 2 || board_height < 2 {
+            return Err(GameError::invalid_board(board_width, board_height, "board is too small"));
         }
 
-        if board_width % 2 != 0 {
-            panic!("Bad board width");
+        if config.snake_length == 0 {
+            return Err(GameError::invalid_board(board_width, board_height, "snake must be at least 1 long"));
         }
 
-        let snake = Snake::new(SNAKE_HEAD);
+        if config.apple_count == 0 {
+            return Err(GameError::invalid_board(board_width, board_height, "there must be at least 1 apple"));
+        }
 
-        let mut gm = Game {
-            snake: snake,
-            apple: Point::new(0, 0),
+        let head = start_head(board_width, board_height, config.snake_length);
+        if head.x >= board_width as i32 {
+            return Err(GameError::invalid_board(board_width, board_height, "snake does not fit on the board"));
+        }
 
-            ham_cycle: HamiltonMatrix::new_filled(board_width, board_height),
-            a_star_path: vec![],
-            a_star_current: 1,
+        let snake = Snake::new(head, config.snake_length, board_width, board_height, config.topology);
+        let mut rng = StdRng::seed_from_u64(config.seed);
 
-            min_x: min_x,
-            min_y: min_y,
-            max_x: max_x,
-            max_y: max_y,
+        let mut walls = match &config.walls {
+            Some(walls) if walls.get_width() != board_width || walls.get_height() != board_height => {
+                return Err(GameError::invalid_board(
+                    board_width,
+                    board_height,
+                    &format!("map is {}x{}", walls.get_width(), walls.get_height()),
+                ));
+            }
+            Some(walls) => walls.clone(),
+            None => Matrix::new(board_width, board_height),
+        };
+        let mut snake_cells = vec![];
+        snake.for_each_segment(|pt, _| snake_cells.push(pt));
+        if snake_cells.iter().any(|pt| walls[*pt]) {
+            return Err(GameError::invalid_board(board_width, board_height, "snake starts on a wall"));
+        }
+        if config.obstacles > 0 {
+            add_random_blocks(&mut walls, config.obstacles, &snake_cells, &mut rng);
+        }
+        let open_cells = walls.cells().filter(|wall| !**wall).count();
+
+        let mut gm = Game {
+            snake: snake,
+            apples: Vec::with_capacity(config.apple_count),
+            spawn: config.spawn,
+            strategy: strategy,
 
             board_width: board_width,
             board_height: board_height,
+            walls: walls,
+            open_cells: open_cells,
+
+            seed: config.seed,
+            rng: rng,
+
+            steps: 0,
+            last_apple_step: 0,
+            stall_limit: config
+                .stall_limit
+                .unwrap_or(2 * open_cells as u64),
+            over: None,
 
             running: false,
-            tick_speed: 20,
+            tick_speed: config.tick_speed,
+        };
 
-            current_path_mode: path_mode,
+        for _ in 0..config.apple_count {
+            let apple = gm
+                .new_apple_point()
+                .ok_or_else(|| GameError::invalid_board(board_width, board_height, "no room for the apples"))?;
+            gm.apples.push(apple);
+        }
+        let view = GameView {
+            snake: &gm.snake,
+            apples: &gm.apples,
+            walls: &gm.walls,
+            board_width: gm.board_width,
+            board_height: gm.board_height,
+            topology: gm.snake.topology(),
         };
+        gm.strategy.on_reset(&view, &mut gm.rng)?;
+        return Ok(gm);
+    }
 
-        gm.apple = gm.new_apple_point();
-        gm.a_star_path =
-            a_star_path(&gm.apple, &gm.snake, board_width, board_height).expect("Bad path");
-        return gm;
+    fn end(&mut self, reason: EndReason) -> TickOutcome {
+        let over = GameOver {
+            reason: reason,
+            steps: self.steps,
+            length: self.snake.size(),
+        };
+        self.over = Some(over);
+        self.running = false;
+        return TickOutcome::Over(over);
     }
 
-    pub fn tick(&mut self) -> bool {
-        if self.snake.check_collide() {
-            return false;
+    /// Applies the rules to the snake's last move.
+    pub fn tick(&mut self) -> TickOutcome {
+        if let Some(over) = self.over {
+            return TickOutcome::Over(over);
         }
 
-        if self.min_x + self.snake.get_head().x < self.min_x
-            || self.min_x + self.snake.get_head().x > self.max_x
-        {
-            return false;
+        let head = self.snake.get_head();
+        if head.x < 0 || head.x >= self.board_width as i32 {
+            return self.end(EndReason::HitWall);
         }
 
-        if self.min_y + self.snake.get_head().y < self.min_y
-            || self.min_y + self.snake.get_head().y > self.max_y
-        {
-            return false;
+        if head.y < 0 || head.y >= self.board_height as i32 {
+            return self.end(EndReason::HitWall);
         }
 
-        if self.snake.get_head() == &self.apple {
-            let board_max = self.ham_cycle.get_width() * self.ham_cycle.get_height();
-            if self.snake.size() == board_max - 2 {
-                return false;
-            }
-            self.snake.expand();
-            self.apple = self.new_apple_point();
-            if self.current_path_mode == PathMode::ASTAR {
-                let pos_path = a_star_path(
-                    &self.apple,
-                    &self.snake,
-                    self.board_width,
-                    self.board_height,
-                );
-                if pos_path.is_none() {
-                    self.running = false;
-                } else {
-                    self.a_star_path = pos_path.unwrap();
+        if self.walls[*head] {
+            return self.end(EndReason::HitWall);
+        }
+
+        if self.snake.check_collide() {
+            return self.end(EndReason::HitSelf);
+        }
+
+        let eaten = match self.apples.iter().position(|apple| apple == self.snake.get_head()) {
+            Some(eaten) => eaten,
+            None => {
+                if self.steps - self.last_apple_step >= self.stall_limit {
+                    return self.end(EndReason::Stalled);
                 }
-                self.a_star_current = 1;
+                return TickOutcome::Continue;
             }
+        };
+
+        self.last_apple_step = self.steps;
+        if self.snake.size() + 1 >= self.open_cells {
+            return self.end(EndReason::Won);
         }
+        self.snake.expand();
+        // once the snake and the apples left cover the board, the snake
+        // can't make room to reach them
+        self.apples[eaten] = match self.new_apple_point() {
+            Some(apple) => apple,
+            None => return self.end(EndReason::Won),
+        };
+        let view = GameView {
+            snake: &self.snake,
+            apples: &self.apples,
+            walls: &self.walls,
+            board_width: self.board_width,
+            board_height: self.board_height,
+            topology: self.snake.topology(),
+        };
+        self.strategy.on_apple_eaten(&view);
 
-        return true;
+        return TickOutcome::AteApple;
     }
 
-    pub fn draw(&self) {
-        ncurses::erase();
-        let ham_v = self
-            .ham_cycle
-            .get(self.snake.get_head().x, self.snake.get_head().y)
-            .unwrap();
-        let apple_v = self.ham_cycle.get(self.apple.x, self.apple.y).unwrap();
-
-        ncurses::mvprintw(
-            0,
-            0,
-            &format!(
-                "Apple={:?}, SnakeLen={:}, Board={}x{}, Speed={}, HamV={:03}, AppleV={:03}, HeadTail={:03}",
-                self.apple,
-                self.snake.size(),
-                self.ham_cycle.get_width(),
-                self.ham_cycle.get_height(),
-                self.tick_speed,
-                ham_v,
-                apple_v,
-                self.tail_mod_dist(*ham_v)
-            ),
-        );
-        self.draw_border();
-
-        // self.draw_cycle();
-        // self.draw_astar();
+    /// Moves the snake the way the strategy chooses and then applies the
+    /// rules. A strategy that can't reach the apple ends the game; any other
+    /// strategy failure is returned as an error.
+    pub fn step(&mut self) -> Result<TickOutcome, GameError> {
+        if let Some(over) = self.over {
+            return Ok(TickOutcome::Over(over));
+        }
 
-        self.snake.draw(self.min_x, self.min_y);
-        ncurses::mvhline(
-            self.min_y + self.apple.y,
-            self.min_x + self.apple.x,
-            ncurses::ACS_CKBOARD(),
-            1,
-        );
-        ncurses::refresh();
+        match self.move_snake() {
+            Ok(()) => Ok(self.tick()),
+            Err(GameError::UnreachableApple) => Ok(self.end(EndReason::NoPath)),
+            Err(err) => Err(err),
+        }
     }
 
-    fn draw_astar(&self) {
-        for pt in &self.a_star_path {
-            ncurses::mvhline(
-                self.min_y + pt.y,
-                self.min_x + pt.x,
-                ncurses::ACS_BLOCK(),
-                1,
-            );
+    pub fn view(&self) -> GameView<'_> {
+        GameView {
+            snake: &self.snake,
+            apples: &self.apples,
+            walls: &self.walls,
+            board_width: self.board_width,
+            board_height: self.board_height,
+            topology: self.snake.topology(),
         }
     }
 
-    fn draw_border(&self) {
-        ncurses::mvhline(
-            self.min_y - 1,
-            self.min_x - 1,
-            ncurses::ACS_HLINE(),
-            self.max_x - self.min_x + 2,
-        );
-        ncurses::mvhline(
-            self.max_y + 1,
-            self.min_x - 1,
-            ncurses::ACS_HLINE(),
-            self.max_x - self.min_x + 2,
+    pub fn status_line(&self) -> String {
+        let status = format!(
+            "Seed={}, Apples={:?}, SnakeLen={:}, Board={}x{}, Speed={}, Strategy={}, {}",
+            self.seed,
+            self.apples,
+            self.snake.size(),
+            self.board_width,
+            self.board_height,
+            self.tick_speed,
+            self.strategy.name(),
+            self.strategy.status(&self.view())
         );
 
-        ncurses::mvvline(
-            self.min_y - 1,
-            self.min_x - 1,
-            ncurses::ACS_VLINE(),
-            self.max_y - self.min_y + 2,
-        );
-        ncurses::mvvline(
-            self.min_y - 1,
-            self.max_x + 1,
-            ncurses::ACS_VLINE(),
-            self.max_y - self.min_y + 2,
-        );
+        match self.over {
+            Some(over) => format!("{}, GameOver={:?} after {} steps", status, over.reason, over.steps),
+            None => status,
+        }
+    }
 
-        ncurses::mvhline(self.min_y - 1, self.min_x - 1, ncurses::ACS_ULCORNER(), 1);
-        ncurses::mvhline(self.max_y + 1, self.min_x - 1, ncurses::ACS_LLCORNER(), 1);
-        ncurses::mvhline(self.max_y + 1, self.max_x + 1, ncurses::ACS_LRCORNER(), 1);
-        ncurses::mvhline(self.min_y - 1, self.max_x + 1, ncurses::ACS_URCORNER(), 1);
+    pub fn steps(&self) -> u64 {
+        self.steps
     }
 
-    fn is_dir_next(&self, currid: &u32, dir: &Direction) -> bool {
-        let test_pt = self.snake.get_head().dir_adj(dir);
+    /// Search totals from the strategy, if it searches.
+    pub fn search_stats(&self) -> Option<SearchStats> {
+        self.strategy.search_stats()
+    }
 
-        if let Some(other) = self.ham_cycle.get(test_pt.x, test_pt.y) {
-            if other == &(currid + 1) {
-                return true;
-            }
+    pub fn game_over(&self) -> Option<GameOver> {
+        self.over
+    }
+
+    pub fn snapshot(&self) -> GameSnapshot {
+        let mut snake = Vec::with_capacity(self.snake.size() + 1);
+        self.snake.for_each_segment(|pt, _| snake.push(pt));
+
+        GameSnapshot {
+            board_width: self.board_width,
+            board_height: self.board_height,
+            snake: snake,
+            apples: self.apples.clone(),
+            walls: self.walls.enumerate().filter(|(_, wall)| **wall).map(|(pt, _)| pt).collect(),
+            steps: self.steps,
+            over: self.over,
         }
-        return false;
     }
 
-    pub fn move_snake(&mut self) {
-        match self.current_path_mode {
-            PathMode::ASTAR => self.move_snake_astar(),
-            PathMode::HAMILTON => self.move_snake_ham(),
-        };
+    pub fn seed(&self) -> u64 {
+        self.seed
     }
 
-    fn move_snake_astar(&mut self) {
-        let next_dir = self
-            .snake
-            .get_head()
-            .in_dir(&self.a_star_path[self.a_star_current]);
-        self.a_star_current += 1;
-        self.snake.move_dir(&next_dir);
+    pub fn snake(&self) -> &Snake {
+        &self.snake
     }
 
-    fn move_snake_ham(&mut self) {
-        let currid = *self
-            .ham_cycle
-            .get(self.snake.get_head().x, self.snake.get_head().y)
-            .unwrap();
-        let apple_val = *self
-            .ham_cycle
-            .get(self.apple.x, self.apple.y)
-            .expect("Apple not on board");
-
-        let mut closest_path: Option<(u32, Direction)> = None;
-        for dir in Direction::all() {
-            let test_pt = self.snake.get_head().dir_adj(dir);
-
-            if let Some(other) = self.ham_cycle.get(test_pt.x, test_pt.y) {
-                // don't go past apple
-                if apple_val > currid && other > &apple_val {
-                    continue;
-                }
-                //    dont go backwards
-                if other < &currid {
-                    continue;
-                }
+    pub fn apples(&self) -> &[Point] {
+        &self.apples
+    }
 
-                // get largest value
-                if closest_path.is_some() && &closest_path.unwrap().0 > other {
-                    continue;
-                }
+    pub fn walls(&self) -> &Matrix<bool> {
+        &self.walls
+    }
 
-                // try not to turn into self
-                if self.snake.on_snake(test_pt) {
-                    continue;
-                }
+    pub fn board_width(&self) -> usize {
+        self.board_width
+    }
 
-                // don't jump too close to tail
-                if self.tail_mod_dist(*other) < 5 {
-                    continue;
-                }
+    pub fn board_height(&self) -> usize {
+        self.board_height
+    }
 
-                closest_path = Some((*other, *dir));
-            }
-        }
+    pub fn move_snake(&mut self) -> Result<(), GameError> {
+        let view = GameView {
+            snake: &self.snake,
+            apples: &self.apples,
+            walls: &self.walls,
+            board_width: self.board_width,
+            board_height: self.board_height,
+            topology: self.snake.topology(),
+        };
+        let dir = self.strategy.next_direction(&view)?;
+        self.snake.move_dir(&dir);
+        self.steps += 1;
+        return Ok(());
+    }
 
-        if closest_path.is_some() {
-            self.snake.move_dir(&closest_path.unwrap().1);
-            return;
-        }
+    /// Where the next apple goes, off the walls, the snake and the other
+    /// apples.
+    fn new_apple_point(&mut self) -> Option<Point> {
+        let head = *self.snake.get_head();
+        let walls = &self.walls;
+        let snake = &self.snake;
+        let apples = &self.apples;
+        return new_apple_point(
+            self.spawn,
+            self.board_width,
+            self.board_height,
+            &[head],
+            |pt| !walls[pt] && !snake.on_snake(pt) && !apples.contains(&pt),
+            &mut self.rng,
+        );
+    }
+}
 
-        // Check for next num in cycle
-        for dir in Direction::all() {
-            if self.is_dir_next(&currid, dir) {
-                self.snake.move_dir(dir);
-                return;
-            }
+/// A cell picked uniformly from those of the `width` by `height` board
+/// where `is_free` holds, or `None` once there are none left.
+fn random_free_cell<F: Fn(Point) -> bool>(width: usize, height: usize, is_free: &F, rng: &mut StdRng) -> Option<Point> {
+    // guessing finds a free cell quickly while the board is mostly empty
+    for _ in 0..APPLE_GUESSES {
+        let pt = Point::new(rng.gen_range(0, width) as i32, rng.gen_range(0, height) as i32);
+        if is_free(pt) {
+            return Some(pt);
         }
+    }
 
-        // if we hit end, find pt 1
-        for dir in Direction::all() {
-            if self.is_dir_next(&0, dir) {
-                self.snake.move_dir(dir);
-                return;
+    let mut free = vec![];
+    for y in 0..height {
+        for x in 0..width {
+            let pt = Point::new(x as i32, y as i32);
+            if is_free(pt) {
+                free.push(pt);
             }
         }
-
-        panic!("Reach end!");
     }
+    return free.choose(rng).copied();
+}
 
-    fn new_apple_point(&self) -> Point {
-        let x = rand::random::<u8>();
-        let y = rand::random::<u8>();
+/// Where the next apple goes under `spawn`, or `None` if the board is full.
+/// `SpawnPolicy::FarFromHead` keeps away from the nearest of `heads`.
+pub(crate) fn new_apple_point<F: Fn(Point) -> bool>(
+    spawn: SpawnPolicy,
+    width: usize,
+    height: usize,
+    heads: &[Point],
+    is_free: F,
+    rng: &mut StdRng,
+) -> Option<Point> {
+    let first = random_free_cell(width, height, &is_free, rng)?;
+    if spawn == SpawnPolicy::Uniform {
+        return Some(first);
+    }
 
-        let screen_x: u8 = (self.max_x - self.min_x) as u8;
-        let screen_y: u8 = (self.max_y - self.min_y) as u8;
+    let nearest_head = |pt: &Point| heads.iter().map(|head| pt.manhattan(head)).min().unwrap_or(0);
+    let mut best = first;
+    for _ in 1..FAR_CANDIDATES {
+        let pt = random_free_cell(width, height, &is_free, rng)?;
+        if nearest_head(&pt) > nearest_head(&best) {
+            best = pt;
+        }
+    }
+    return Some(best);
+}
 
-        let mut pos_point = Point {
-            x: ((x % screen_x) as u8) as i32,
-            y: ((y % screen_y) as u8) as i32,
-        };
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        let mut allowed = true;
-        self.snake.for_each_segment(|pt, _| {
-            if pt == pos_point {
-                allowed = false;
-            }
-        });
+    const POLICIES: [SpawnPolicy; 2] = [SpawnPolicy::Uniform, SpawnPolicy::FarFromHead];
 
-        if !allowed {
-            pos_point = self.new_apple_point();
+    #[test]
+    fn no_apple_on_a_full_board() {
+        for spawn in POLICIES.iter() {
+            let mut rng = StdRng::seed_from_u64(1);
+            assert_eq!(new_apple_point(*spawn, 5, 4, &[Point::new(0, 0)], |_| false, &mut rng), None);
         }
-        return pos_point;
     }
 
-    fn tail_mod_dist(&self, test_val: u32) -> i32 {
-        let board_max = (self.ham_cycle.get_width() * self.ham_cycle.get_height()) as u32;
-        let tail_pos = self.snake.get_tail();
-        let tail_id = self
-            .ham_cycle
-            .get(tail_pos.x, tail_pos.y)
-            .expect("Bad tail unwrap");
-        let head_id = self
-            .ham_cycle
-            .get(self.snake.get_head().x, self.snake.get_head().y)
-            .unwrap();
-
-        let test_dist = if tail_id < head_id && tail_id < &test_val && head_id > &test_val {
-            -1
-        } else if tail_id > head_id && tail_id < &test_val && head_id < &test_val {
-            -1
-        } else if tail_id < &test_val {
-            ((board_max - test_val) + tail_id) as i32
-        } else {
-            (tail_id - test_val) as i32
-        };
+    #[test]
+    fn apple_goes_on_the_last_free_cell() {
+        let last = Point::new(3, 2);
+        for spawn in POLICIES.iter() {
+            for seed in 0..20 {
+                let mut rng = StdRng::seed_from_u64(seed);
+                let apple = new_apple_point(*spawn, 5, 4, &[Point::new(0, 0)], |pt| pt == last, &mut rng);
+                assert_eq!(apple, Some(last));
+            }
+        }
+    }
 
-        return test_dist;
+    #[test]
+    fn apples_only_go_on_free_cells() {
+        let is_free = |pt: Point| (pt.x + pt.y) % 3 == 0;
+        for spawn in POLICIES.iter() {
+            let mut rng = StdRng::seed_from_u64(7);
+            for _ in 0..200 {
+                let apple = new_apple_point(*spawn, 6, 5, &[Point::new(2, 2)], is_free, &mut rng);
+                assert!(apple.is_some_and(is_free));
+            }
+        }
     }
 }
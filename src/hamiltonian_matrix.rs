@@ -1,9 +1,15 @@
+#![allow(clippy::needless_return, clippy::redundant_field_names)]
+
 use crate::direction::Direction;
+use crate::error::GameError;
 use crate::matrix::Matrix;
 use crate::point::Point;
+use rand::Rng;
 use std::cmp::Ordering;
 use std::collections::BinaryHeap;
+use std::fmt;
 
+#[derive(Clone, Default)]
 struct GridWeightNode {
     right: u8,
     down: u8,
@@ -31,6 +37,7 @@ impl PartialOrd for Connection {
     }
 }
 
+#[derive(Clone, Default)]
 struct PrimTreeNode {
     up: bool,
     right: bool,
@@ -40,53 +47,28 @@ struct PrimTreeNode {
     in_tree: bool,
 }
 
-fn generate_rand_weights(width: usize, height: usize) -> Matrix<GridWeightNode> {
+fn generate_rand_weights<R: Rng>(width: usize, height: usize, rng: &mut R) -> Matrix<GridWeightNode> {
     let mut matrix = Matrix::new(width, height);
 
+    // column by column, so a seed keeps drawing the same maze
     for x in 0..width {
         for y in 0..height {
-            matrix.set(
-                x,
-                y,
-                GridWeightNode {
-                    right: rand::random(),
-                    down: rand::random(),
-                },
-            );
-        }
-    }
-
-    return matrix;
-}
-
-fn generate_empty_prim_graph(width: usize, height: usize) -> Matrix<PrimTreeNode> {
-    let mut matrix = Matrix::new(width, height);
-
-    for x in 0..width {
-        for y in 0..height {
-            matrix.set(
-                x,
-                y,
-                PrimTreeNode {
-                    up: false,
-                    right: false,
-                    down: false,
-                    left: false,
-
-                    in_tree: false,
-                },
-            );
+            matrix[Point::new(x as i32, y as i32)] = GridWeightNode {
+                right: rng.gen(),
+                down: rng.gen(),
+            };
         }
     }
 
     return matrix;
 }
 
+#[allow(dead_code)]
 fn print_prim_graph(graph: &Matrix<PrimTreeNode>) {
     for y in 0..graph.get_height() {
         // print tops
         for x in 0..graph.get_width() {
-            let pt = graph.get(x, y).expect("graph node not found");
+            let pt = &graph[Point::new(x as i32, y as i32)];
             print!(" ");
             if pt.up {
                 print!("#");
@@ -100,7 +82,7 @@ fn print_prim_graph(graph: &Matrix<PrimTreeNode>) {
 
         // print l r self
         for x in 0..graph.get_width() {
-            let pt = graph.get(x, y).expect("graph node not found");
+            let pt = &graph[Point::new(x as i32, y as i32)];
             if pt.left {
                 print!("#");
             } else {
@@ -118,7 +100,7 @@ fn print_prim_graph(graph: &Matrix<PrimTreeNode>) {
 
         // print tops
         for x in 0..graph.get_width() {
-            let pt = graph.get(x, y).expect("graph node not found");
+            let pt = &graph[Point::new(x as i32, y as i32)];
             print!(" ");
             if pt.down {
                 print!("#");
@@ -139,7 +121,7 @@ fn add_all_dirs(
 ) {
     // add up
     let up_pos = current.add(0, -1);
-    if let Some(up_node) = weights.get(up_pos.x, up_pos.y) {
+    if let Some(up_node) = weights.get(&up_pos) {
         node_queue.push(Connection {
             first: *current,
             second: up_pos,
@@ -150,7 +132,7 @@ fn add_all_dirs(
 
     // add left
     let left_pos = current.add(-1, 0);
-    if let Some(up_node) = weights.get(left_pos.x, left_pos.y) {
+    if let Some(up_node) = weights.get(&left_pos) {
         node_queue.push(Connection {
             first: *current,
             second: left_pos,
@@ -159,7 +141,7 @@ fn add_all_dirs(
         });
     }
 
-    if let Some(own_node) = weights.get(current.x, current.y) {
+    if let Some(own_node) = weights.get(current) {
         // add down
         node_queue.push(Connection {
             first: *current,
@@ -178,12 +160,59 @@ fn add_all_dirs(
     }
 }
 
-fn create_rand_prim_tree(width: usize, height: usize) -> Matrix<PrimTreeNode> {
-    let weights = generate_rand_weights(width, height);
-    let mut prim_nodes = generate_empty_prim_graph(width, height);
+/// Weights that follow a Hilbert curve laid over the grid. Cells next to each
+/// other along the curve get the cheapest edges, so Prim's algorithm grows the
+/// maze along the curve.
+fn generate_hilbert_weights(width: usize, height: usize) -> Matrix<GridWeightNode> {
+    let mut side = 1;
+    while side < width.max(height) {
+        side *= 2;
+    }
+
+    let weight = |a: usize, b: usize| (a as i64 - b as i64).unsigned_abs().min(u8::MAX as u64) as u8;
+    return Matrix::from_fn(width, height, |pt| {
+        let (x, y) = (pt.x as usize, pt.y as usize);
+        let d = hilbert_index(side, x, y);
+        // edges off the board are never followed
+        let right = if x + 1 < width { weight(d, hilbert_index(side, x + 1, y)) } else { u8::MAX };
+        let down = if y + 1 < height { weight(d, hilbert_index(side, x, y + 1)) } else { u8::MAX };
+        GridWeightNode { right: right, down: down }
+    });
+}
+
+/// Distance along a Hilbert curve filling a `side` by `side` square, where
+/// `side` is a power of two.
+fn hilbert_index(side: usize, x: usize, y: usize) -> usize {
+    let (mut x, mut y) = (x, y);
+    let mut d = 0;
+    let mut s = side / 2;
+    while s > 0 {
+        let rx = (x & s > 0) as usize;
+        let ry = (y & s > 0) as usize;
+        d += s * s * ((3 * rx) ^ ry);
+
+        // rotate the quadrant so the curve inside it starts in the corner
+        if ry == 0 {
+            if rx == 1 {
+                x = side - 1 - x;
+                y = side - 1 - y;
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+        s /= 2;
+    }
+    return d;
+}
+
+/// Grows a maze from `root` over the nodes set in `open`.
+fn create_prim_tree(weights: &Matrix<GridWeightNode>, open: &Matrix<bool>, root: Point) -> Matrix<PrimTreeNode> {
+    let mut prim_nodes: Matrix<PrimTreeNode> = Matrix::new(weights.get_width(), weights.get_height());
     let mut node_queue = BinaryHeap::new();
 
-    add_all_dirs(&Point::new(0, 0), &weights, &mut node_queue);
+    // the root is in the tree from the start, otherwise a later connection
+    // back into it closes a loop in the maze
+    prim_nodes[root].in_tree = true;
+    add_all_dirs(&root, weights, &mut node_queue);
 
     loop {
         let next_conn = node_queue.pop();
@@ -193,16 +222,14 @@ fn create_rand_prim_tree(width: usize, height: usize) -> Matrix<PrimTreeNode> {
         let next_conn = next_conn.unwrap();
 
         // first node must be in graph
-        let second_node = prim_nodes.get(next_conn.second.x, next_conn.second.y);
+        let second_node = prim_nodes.get(&next_conn.second);
 
-        // discard stale connections
-        if second_node.is_none() || second_node.unwrap().in_tree {
+        // discard stale connections, and ones into walled off nodes
+        if second_node.map_or(true, |node| node.in_tree) || !open[next_conn.second] {
             continue;
         }
         {
-            let first_node_mut = prim_nodes
-                .get_mut(next_conn.first.x, next_conn.first.y)
-                .unwrap();
+            let first_node_mut = &mut prim_nodes[next_conn.first];
             match next_conn.direction {
                 Direction::UP => first_node_mut.up = true,
                 Direction::RIGHT => first_node_mut.right = true,
@@ -212,9 +239,7 @@ fn create_rand_prim_tree(width: usize, height: usize) -> Matrix<PrimTreeNode> {
         }
 
         {
-            let second_node_mut = prim_nodes
-                .get_mut(next_conn.second.x, next_conn.second.y)
-                .unwrap();
+            let second_node_mut = &mut prim_nodes[next_conn.second];
             match next_conn.direction {
                 Direction::UP => second_node_mut.down = true,
                 Direction::RIGHT => second_node_mut.left = true,
@@ -225,21 +250,24 @@ fn create_rand_prim_tree(width: usize, height: usize) -> Matrix<PrimTreeNode> {
             second_node_mut.in_tree = true;
         }
 
-        add_all_dirs(&next_conn.second, &weights, &mut node_queue);
+        add_all_dirs(&next_conn.second, weights, &mut node_queue);
     }
 
     return prim_nodes;
 }
 
-fn hamilton_from_prim_nodes(prim_nodes: &Matrix<PrimTreeNode>) -> Matrix<u32> {
+/// Follows the right hand wall of the maze around from the top left corner
+/// of `root`, numbering the cells passed.
+fn hamilton_from_prim_nodes(prim_nodes: &Matrix<PrimTreeNode>, root: Point) -> Matrix<u32> {
     let mut ham_mat: Matrix<u32> = Matrix::new(prim_nodes.get_width() * 2, prim_nodes.get_height() * 2);
-    let mut ham_loc = Point::new(0, 0);
-    let mut og_loc = Point::new(0, 0);
+    let start = Point::new(root.x * 2, root.y * 2);
+    let mut ham_loc = start;
+    let mut og_loc = root;
     let mut id = 0;
     let mut curr_dir = Direction::RIGHT;
     loop {
-        ham_mat.set(ham_loc.x, ham_loc.y, id);
-        let og_node = prim_nodes.get(og_loc.x, og_loc.y).unwrap();
+        ham_mat[ham_loc] = id;
+        let og_node = &prim_nodes[og_loc];
         match curr_dir {
             Direction::UP => {
                 if og_node.left {
@@ -247,7 +275,7 @@ fn hamilton_from_prim_nodes(prim_nodes: &Matrix<PrimTreeNode>) -> Matrix<u32> {
                 } else {
                     ham_loc = ham_loc.add(0, -1);
                     id += 1;
-                    ham_mat.set(ham_loc.x, ham_loc.y, id);
+                    ham_mat[ham_loc] = id;
                 }
             }
             Direction::RIGHT => {
@@ -256,7 +284,7 @@ fn hamilton_from_prim_nodes(prim_nodes: &Matrix<PrimTreeNode>) -> Matrix<u32> {
                 } else {
                     ham_loc = ham_loc.add(1, 0);
                     id += 1;
-                    ham_mat.set(ham_loc.x, ham_loc.y, id);
+                    ham_mat[ham_loc] = id;
                 }
             }
             Direction::DOWN => {
@@ -265,7 +293,7 @@ fn hamilton_from_prim_nodes(prim_nodes: &Matrix<PrimTreeNode>) -> Matrix<u32> {
                 } else {
                     ham_loc = ham_loc.add(0, 1);
                     id += 1;
-                    ham_mat.set(ham_loc.x, ham_loc.y, id);
+                    ham_mat[ham_loc] = id;
                 }
             }
             Direction::LEFT => {
@@ -274,7 +302,7 @@ fn hamilton_from_prim_nodes(prim_nodes: &Matrix<PrimTreeNode>) -> Matrix<u32> {
                 } else {
                     ham_loc = ham_loc.add(-1, 0);
                     id += 1;
-                    ham_mat.set(ham_loc.x, ham_loc.y, id);
+                    ham_mat[ham_loc] = id;
                 }
             }
         }
@@ -318,7 +346,7 @@ fn hamilton_from_prim_nodes(prim_nodes: &Matrix<PrimTreeNode>) -> Matrix<u32> {
             }
         }
 
-        if ham_loc == Point::new(0, 0) {
+        if ham_loc == start {
             break;
         }
     }
@@ -326,15 +354,542 @@ fn hamilton_from_prim_nodes(prim_nodes: &Matrix<PrimTreeNode>) -> Matrix<u32> {
     return ham_mat;
 }
 
+/// Cells of `ham_mat` in cycle order.
+fn cycle_order(ham_mat: &Matrix<u32>) -> Vec<Point> {
+    let mut cells: Vec<(u32, Point)> = ham_mat
+        .enumerate()
+        .filter(|(_, id)| **id != 0)
+        .map(|(pt, id)| (*id, pt))
+        .collect();
+    cells.sort_by_key(|(id, _)| *id);
+    return cells.into_iter().map(|(_, pt)| pt).collect();
+}
+
+/// Numbers the cycle the same way `hamilton_from_prim_nodes` does, counting
+/// up from 1 and ending back on (0, 0).
+fn matrix_from_order(order: &[Point], width: usize, height: usize) -> Matrix<u32> {
+    let mut ham_mat = Matrix::new(width, height);
+    let origin = order.iter().position(|pt| *pt == Point::new(0, 0)).unwrap_or(0);
+    for i in 0..order.len() {
+        let pt = order[(origin + 1 + i) % order.len()];
+        ham_mat[pt] = i as u32 + 1;
+    }
+    return ham_mat;
+}
+
+/// Splices a strip of cells onto the cycle. Wherever the cycle steps between
+/// a pair of cells that `detour` maps to a pair of new cells, it goes out
+/// through the new cells instead.
+fn splice_strip<F: Fn(&Point, &Point) -> Option<(Point, Point)>>(order: &[Point], detour: F) -> Vec<Point> {
+    let mut spliced = Vec::with_capacity(order.len() * 2);
+    for i in 0..order.len() {
+        let from = order[i];
+        let to = order[(i + 1) % order.len()];
+        spliced.push(from);
+        if let Some((out, back)) = detour(&from, &to) {
+            spliced.push(out);
+            spliced.push(back);
+        }
+    }
+    return spliced;
+}
+
+/// Boustrophedon cycle: sweeps back and forth along the rows, leaving the
+/// first column free as the way back to the start.
+fn zigzag_order(width: usize, height: usize) -> Vec<Point> {
+    if height % 2 != 0 {
+        // sweep the columns instead, as the rows can't pair up
+        return zigzag_order(height, width)
+            .into_iter()
+            .map(|pt| Point::new(pt.y, pt.x))
+            .collect();
+    }
+
+    let mut order = Vec::with_capacity(width * height);
+    for y in 0..height {
+        let row: Vec<usize> = if y % 2 == 0 {
+            (1..width).collect()
+        } else {
+            (1..width).rev().collect()
+        };
+        for x in row {
+            order.push(Point::new(x as i32, y as i32));
+        }
+    }
+    for y in (0..height).rev() {
+        order.push(Point::new(0, y as i32));
+    }
+    return order;
+}
+
+/// Cells around the edge of the rectangle from `min` to `max` inclusive,
+/// clockwise from the top left.
+fn ring_order(min: Point, max: Point) -> Vec<Point> {
+    let mut ring = vec![];
+    for x in min.x..=max.x {
+        ring.push(Point::new(x, min.y));
+    }
+    for y in min.y + 1..=max.y {
+        ring.push(Point::new(max.x, y));
+    }
+    for x in (min.x..max.x).rev() {
+        ring.push(Point::new(x, max.y));
+    }
+    for y in (min.y + 1..max.y).rev() {
+        ring.push(Point::new(min.x, y));
+    }
+    return ring;
+}
+
+/// Merges the cycle `piece` into `order`. Some step of `piece` has to run
+/// alongside a step of `order`, so the two can be swapped for the steps
+/// joining their ends.
+fn merge_cycle(order: &[Point], piece: &[Point]) -> Option<Vec<Point>> {
+    let mut pos: Vec<Option<usize>> = vec![];
+    let width = order.iter().chain(piece).map(|pt| pt.x).max()? as usize + 1;
+    let height = order.iter().chain(piece).map(|pt| pt.y).max()? as usize + 1;
+    pos.resize(width * height, None);
+    for (i, pt) in order.iter().enumerate() {
+        pos[pt.y as usize * width + pt.x as usize] = Some(i);
+    }
+    let pos_of = |pt: &Point| {
+        if pt.x < 0 || pt.y < 0 || pt.x as usize >= width || pt.y as usize >= height {
+            return None;
+        }
+        return pos[pt.y as usize * width + pt.x as usize];
+    };
+
+    for i in 0..piece.len() {
+        let from = piece[i];
+        let to = piece[(i + 1) % piece.len()];
+        for dir in Direction::all() {
+            let (u, v) = match (pos_of(&from.dir_adj(dir)), pos_of(&to.dir_adj(dir))) {
+                (Some(u), Some(v)) => (u, v),
+                _ => continue,
+            };
+
+            // walk the piece the long way round, from one end of the dropped
+            // step to the other
+            let mut detour: Vec<Point> = (0..piece.len())
+                .map(|k| piece[(i + piece.len() - k) % piece.len()])
+                .collect();
+            let after = if v == (u + 1) % order.len() {
+                u
+            } else if u == (v + 1) % order.len() {
+                detour.reverse();
+                v
+            } else {
+                continue;
+            };
+
+            let mut merged = Vec::with_capacity(order.len() + piece.len());
+            merged.extend_from_slice(&order[..=after]);
+            merged.extend(detour);
+            merged.extend_from_slice(&order[after + 1..]);
+            return Some(merged);
+        }
+    }
+    return None;
+}
+
+/// Concentric rings joined into one cycle. When the shorter side is odd the
+/// rings leave a one cell wide strip in the middle, which is joined on two
+/// cells at a time.
+fn spiral_order(width: usize, height: usize) -> Option<Vec<Point>> {
+    let mut pieces = vec![];
+    let mut k = 0;
+    while width >= 2 * k + 2 && height >= 2 * k + 2 {
+        let min = Point::new(k as i32, k as i32);
+        let max = Point::new((width - 1 - k) as i32, (height - 1 - k) as i32);
+        pieces.push(ring_order(min, max));
+        k += 1;
+    }
+
+    let strip: Vec<Point> = (k..width.saturating_sub(k))
+        .flat_map(|x| (k..height.saturating_sub(k)).map(move |y| Point::new(x as i32, y as i32)))
+        .collect();
+    for pair in strip.chunks(2) {
+        pieces.push(pair.to_vec());
+    }
+
+    let mut pieces = pieces.into_iter();
+    let mut order = pieces.next()?;
+    for piece in pieces {
+        order = merge_cycle(&order, &piece)?;
+    }
+    return Some(order);
+}
+
+/// The ways a hamiltonian cycle can be laid over the board.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum CycleGenerator {
+    /// Outline of a random maze grown with Prim's algorithm.
+    #[default]
+    Prim,
+    /// Back and forth along the rows, or the columns if the rows are odd.
+    Zigzag,
+    /// Concentric rings joined together.
+    Spiral,
+    /// Outline of a maze grown along a Hilbert curve.
+    Hilbert,
+}
+
+pub const CYCLE_NAMES: [&str; 4] = ["prim", "zigzag", "spiral", "hilbert"];
+
+impl CycleGenerator {
+    pub fn from_name(name: &str) -> Option<CycleGenerator> {
+        match name {
+            "prim" => Some(CycleGenerator::Prim),
+            "zigzag" => Some(CycleGenerator::Zigzag),
+            "spiral" => Some(CycleGenerator::Spiral),
+            "hilbert" => Some(CycleGenerator::Hilbert),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            CycleGenerator::Prim => "prim",
+            CycleGenerator::Zigzag => "zigzag",
+            CycleGenerator::Spiral => "spiral",
+            CycleGenerator::Hilbert => "hilbert",
+        }
+    }
+}
+
+/// The first wall, if any, that doesn't fill a whole 2x2 maze cell, or that
+/// lies in the odd column or row the maze doesn't cover.
+fn misaligned_wall(walls: &Matrix<bool>) -> Option<Point> {
+    let even_width = (walls.get_width() / 2 * 2) as i32;
+    let even_height = (walls.get_height() / 2 * 2) as i32;
+    walls.enumerate().find_map(|(pt, wall)| {
+        if !*wall {
+            return None;
+        }
+        if pt.x >= even_width || pt.y >= even_height {
+            return Some(pt);
+        }
+        let corner = Point::new(pt.x / 2 * 2, pt.y / 2 * 2);
+        let block = [corner, corner.add(1, 0), corner.add(0, 1), corner.add(1, 1)];
+        if block.iter().all(|pt| walls[*pt]) {
+            None
+        } else {
+            Some(pt)
+        }
+    })
+}
+
+/// Doubles the maze grown over `weights` into a cycle, then splices on the
+/// odd column or row, if any. Maze nodes with a wall in them are left out,
+/// along with any cut off from the rest, so the cycle can come up short of
+/// covering the board.
+fn cycle_from_maze(walls: &Matrix<bool>, weights: &Matrix<GridWeightNode>) -> HamiltonMatrix {
+    let width = walls.get_width();
+    let height = walls.get_height();
+    let open = Matrix::from_fn(weights.get_width(), weights.get_height(), |node| {
+        let corner = Point::new(node.x * 2, node.y * 2);
+        [corner, corner.add(1, 0), corner.add(0, 1), corner.add(1, 1)]
+            .iter()
+            .all(|pt| !walls[*pt])
+    });
+    let root = match open.enumerate().find(|(_, open)| **open) {
+        Some((root, _)) => root,
+        None => return Matrix::new(width, height),
+    };
+
+    let prim_nodes = create_prim_tree(weights, &open, root);
+    // print_prim_graph(&prim_nodes);
+    let hamilton = hamilton_from_prim_nodes(&prim_nodes, root);
+    // hamilton.print_matrix();
+
+    let even_width = prim_nodes.get_width() * 2;
+    let even_height = prim_nodes.get_height() * 2;
+    if width == even_width && height == even_height {
+        if root == Point::new(0, 0) {
+            return hamilton;
+        }
+        // renumber so the ids end on (0, 0) like the other layouts
+        return matrix_from_order(&cycle_order(&hamilton), width, height);
+    }
+
+    // following the right hand wall, the cycle runs straight down the
+    // last column of every maze cell on the right edge, and along the
+    // bottom row of every maze cell on the bottom edge. Those steps are
+    // where the extra column or row is spliced in.
+    let last_x = even_width as i32 - 1;
+    let last_y = even_height as i32 - 1;
+    let order = cycle_order(&hamilton);
+    let order = if width > even_width {
+        splice_strip(&order, |from, to| {
+            let detour = (Point::new(last_x + 1, from.y), Point::new(last_x + 1, to.y));
+            if from.x != last_x || to.x != last_x || from.y / 2 != to.y / 2 || walls[detour.0] || walls[detour.1] {
+                return None;
+            }
+            return Some(detour);
+        })
+    } else {
+        splice_strip(&order, |from, to| {
+            let detour = (Point::new(from.x, last_y + 1), Point::new(to.x, last_y + 1));
+            if from.y != last_y || to.y != last_y || from.x / 2 != to.x / 2 || walls[detour.0] || walls[detour.1] {
+                return None;
+            }
+            return Some(detour);
+        })
+    };
+
+    return matrix_from_order(&order, width, height);
+}
+
+/// Something wrong with a cycle, found by `HamiltonMatrix::validate`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CycleProblem {
+    /// The cell has no id, which is stored as 0.
+    MissingCell(Point),
+    /// The cell's id is outside `1..=cells`.
+    IdOutOfRange { cell: Point, id: u32 },
+    /// More than one cell has the id.
+    DuplicateId { id: u32, cells: Vec<Point> },
+    /// No cell has the id.
+    MissingId(u32),
+    /// Consecutive ids sit on cells that aren't neighbours.
+    NotAdjacent { id: u32, from: Point, to: Point },
+    /// A wall has an id.
+    OnWall { cell: Point, id: u32 },
+}
+
+impl fmt::Display for CycleProblem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CycleProblem::MissingCell(cell) => write!(f, "cell ({}, {}) has no id", cell.x, cell.y),
+            CycleProblem::IdOutOfRange { cell, id } => {
+                write!(f, "cell ({}, {}) has out of range id {}", cell.x, cell.y, id)
+            }
+            CycleProblem::DuplicateId { id, cells } => {
+                let cells: Vec<String> = cells.iter().map(|c| format!("({}, {})", c.x, c.y)).collect();
+                write!(f, "id {} is on {} cells: {}", id, cells.len(), cells.join(" "))
+            }
+            CycleProblem::MissingId(id) => write!(f, "id {} is on no cell", id),
+            CycleProblem::NotAdjacent { id, from, to } => write!(
+                f,
+                "id {} at ({}, {}) isn't next to the following id at ({}, {})",
+                id, from.x, from.y, to.x, to.y
+            ),
+            CycleProblem::OnWall { cell, id } => write!(f, "wall at ({}, {}) has id {}", cell.x, cell.y, id),
+        }
+    }
+}
+
+/// Position of each cell along the cycle, counting from 1. A cell left at 0
+/// isn't on the cycle.
 pub type HamiltonMatrix = Matrix<u32>;
 
 impl HamiltonMatrix {
-    pub fn new_filled(width: usize, height: usize) -> HamiltonMatrix {
-        let prim_nodes = create_rand_prim_tree(width/2, height/2);
-        // print_prim_graph(&prim_nodes);
-        let hamilton = hamilton_from_prim_nodes(&prim_nodes);
-        // hamilton.print_matrix();
+    /// Checks this is a hamiltonian cycle numbered the way the strategies
+    /// expect: every cell has a distinct id from 1 up to the number of cells,
+    /// each id sits next to the one after it, and the last sits next to 1.
+    /// Lists every problem found, not just the first.
+    pub fn validate(&self) -> Result<(), Vec<CycleProblem>> {
+        return self.validate_around(&Matrix::new(self.get_width(), self.get_height()));
+    }
+
+    /// Like `validate`, for a cycle over every cell except the `walls`,
+    /// which must have no id.
+    pub fn validate_around(&self, walls: &Matrix<bool>) -> Result<(), Vec<CycleProblem>> {
+        let cells = walls.cells().filter(|wall| !**wall).count();
+        let mut problems = vec![];
+
+        let mut id_cells: Vec<Vec<Point>> = vec![vec![]; cells + 1];
+        for (cell, id) in self.enumerate() {
+            if walls[cell] {
+                if *id != 0 {
+                    problems.push(CycleProblem::OnWall { cell: cell, id: *id });
+                }
+            } else if *id == 0 {
+                problems.push(CycleProblem::MissingCell(cell));
+            } else if *id as usize > cells {
+                problems.push(CycleProblem::IdOutOfRange { cell: cell, id: *id });
+            } else {
+                id_cells[*id as usize].push(cell);
+            }
+        }
+
+        for (id, on) in id_cells.iter().enumerate().skip(1) {
+            match on.len() {
+                0 => problems.push(CycleProblem::MissingId(id as u32)),
+                1 => {}
+                _ => problems.push(CycleProblem::DuplicateId {
+                    id: id as u32,
+                    cells: on.clone(),
+                }),
+            }
+        }
+
+        for id in 1..=cells {
+            let next = id % cells + 1;
+            // missing and duplicate ids are already reported
+            if let ([from], [to]) = (id_cells[id].as_slice(), id_cells[next].as_slice()) {
+                if (from.x - to.x).abs() + (from.y - to.y).abs() != 1 {
+                    problems.push(CycleProblem::NotAdjacent {
+                        id: id as u32,
+                        from: *from,
+                        to: *to,
+                    });
+                }
+            }
+        }
+
+        if problems.is_empty() {
+            return Ok(());
+        }
+        return Err(problems);
+    }
+
+    /// A random hamiltonian cycle over a `width` by `height` board.
+    pub fn new_filled<R: Rng>(width: usize, height: usize, rng: &mut R) -> Result<HamiltonMatrix, GameError> {
+        return HamiltonMatrix::generate(CycleGenerator::Prim, width, height, rng);
+    }
+
+    /// A hamiltonian cycle over a `width` by `height` board, laid out by
+    /// `generator`. At least one dimension has to be even, otherwise the
+    /// board has an odd number of cells and no cycle can cover it. Only the
+    /// Prim generator draws from `rng`. Debug builds validate every cycle
+    /// before handing it out.
+    pub fn generate<R: Rng>(
+        generator: CycleGenerator,
+        width: usize,
+        height: usize,
+        rng: &mut R,
+    ) -> Result<HamiltonMatrix, GameError> {
+        return HamiltonMatrix::generate_around(generator, &Matrix::new(width, height), rng);
+    }
+
+    /// Like `generate`, for a cycle over every cell except the `walls`. Only
+    /// the maze layouts can go around walls, and only walls that fill whole
+    /// 2x2 blocks of the maze, as `walls::add_random_blocks` places them.
+    /// Any other wall is reported as misaligned, and walls that cut the
+    /// maze apart as an invalid board.
+    pub fn generate_around<R: Rng>(
+        generator: CycleGenerator,
+        walls: &Matrix<bool>,
+        rng: &mut R,
+    ) -> Result<HamiltonMatrix, GameError> {
+        let width = walls.get_width();
+        let height = walls.get_height();
+        let has_walls = walls.cells().any(|wall| *wall);
+        if width % 2 != 0 && height % 2 != 0 {
+            return Err(GameError::invalid_board(
+                width,
+                height,
+                "hamiltonian cycles need at least one even dimension",
+            ));
+        }
+        if width < 2 || height < 2 {
+            return Err(GameError::invalid_board(width, height, "board is too small for a cycle"));
+        }
+
+        // the mazes cover the largest even board, odd boards get one more
+        // column or row spliced on afterwards
+        let maze = matches!(generator, CycleGenerator::Prim | CycleGenerator::Hilbert);
+        if let Some(pt) = misaligned_wall(walls).filter(|_| maze) {
+            return Err(GameError::MisalignedWall(pt));
+        }
+
+        let maze_width = width / 2;
+        let maze_height = height / 2;
+        let cycle = match generator {
+            CycleGenerator::Prim => {
+                let weights = generate_rand_weights(maze_width, maze_height, rng);
+                cycle_from_maze(walls, &weights)
+            }
+            CycleGenerator::Hilbert => {
+                let weights = generate_hilbert_weights(maze_width, maze_height);
+                cycle_from_maze(walls, &weights)
+            }
+            CycleGenerator::Zigzag | CycleGenerator::Spiral if has_walls => {
+                return Err(GameError::invalid_board(
+                    width,
+                    height,
+                    &format!("the {} layout can't go around walls", generator.name()),
+                ));
+            }
+            CycleGenerator::Zigzag => matrix_from_order(&zigzag_order(width, height), width, height),
+            CycleGenerator::Spiral => {
+                let order = spiral_order(width, height).ok_or_else(|| {
+                    GameError::invalid_board(width, height, "rings couldn't be joined into a spiral")
+                })?;
+                matrix_from_order(&order, width, height)
+            }
+        };
+
+        let open_cells = walls.cells().filter(|wall| !**wall).count();
+        if has_walls && cycle.cells().filter(|id| **id != 0).count() != open_cells {
+            return Err(GameError::invalid_board(
+                width,
+                height,
+                "no hamiltonian cycle could be laid around the walls",
+            ));
+        }
+
+        #[cfg(debug_assertions)]
+        cycle.validate_around(walls).map_err(GameError::InvalidCycle)?;
+
+        return Ok(cycle);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn cycle() -> HamiltonMatrix {
+        let mut rng = StdRng::seed_from_u64(3);
+        HamiltonMatrix::generate(CycleGenerator::Prim, 6, 4, &mut rng).expect("a cycle")
+    }
+
+    fn cell_of(matrix: &HamiltonMatrix, id: u32) -> Point {
+        matrix.enumerate().find(|(_, on)| **on == id).map(|(pt, _)| pt).expect("id on the cycle")
+    }
+
+    #[test]
+    fn generated_cycle_is_valid() {
+        assert_eq!(cycle().validate(), Ok(()));
+    }
+
+    #[test]
+    fn validate_reports_cells_swapped_out_of_order() {
+        let mut matrix = cycle();
+        let second = cell_of(&matrix, 2);
+        let fifth = cell_of(&matrix, 5);
+        matrix[second] = 5;
+        matrix[fifth] = 2;
+
+        // ids 4 apart sit on cells of the same colour, which never touch
+        let problems = matrix.validate().unwrap_err();
+        assert!(problems.contains(&CycleProblem::NotAdjacent {
+            id: 1,
+            from: cell_of(&matrix, 1),
+            to: fifth,
+        }));
+    }
 
-        return hamilton;
+    #[test]
+    fn validate_reports_duplicate_and_missing_ids() {
+        let mut matrix = cycle();
+        let first = cell_of(&matrix, 1);
+        let third = cell_of(&matrix, 3);
+        let sixth = cell_of(&matrix, 6);
+        matrix[third] = 1;
+        matrix[sixth] = 0;
+
+        let problems = matrix.validate().unwrap_err();
+        assert!(problems.iter().any(|problem| match problem {
+            CycleProblem::DuplicateId { id: 1, cells } => cells.contains(&first) && cells.contains(&third),
+            _ => false,
+        }));
+        assert!(problems.contains(&CycleProblem::MissingId(3)));
+        assert!(problems.contains(&CycleProblem::MissingId(6)));
+        assert!(problems.contains(&CycleProblem::MissingCell(sixth)));
     }
 }
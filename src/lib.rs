@@ -0,0 +1,42 @@
+//! A snake simulator driven by pathing AIs.
+//!
+//! A `Game` is built from a `GameConfig` and a boxed `Strategy`, then
+//! advanced with `Game::step` until it returns `TickOutcome::Over`. Nothing
+//! in the library draws to the terminal; front ends implement `Renderer`.
+//! An `Arena` plays several strategies against each other on one board, and
+//! `env::Env` lets a learning agent play one action at a time. `evolve`
+//! trains networks for the neural strategy.
+
+mod a_star;
+mod arena;
+mod direction;
+mod error;
+mod game;
+mod hamiltonian_matrix;
+mod matrix;
+mod neural;
+mod point;
+mod snake;
+mod topology;
+
+pub mod bench;
+pub mod encode;
+pub mod env;
+pub mod evolve;
+pub mod render;
+pub mod strategy;
+pub mod walls;
+
+pub use crate::arena::{Arena, Entrant};
+pub use crate::a_star::{a_star_path, a_star_search, Heuristic, SearchConfig, SearchResult, SearchStats, TieBreak};
+pub use crate::direction::Direction;
+pub use crate::error::GameError;
+pub use crate::game::{EndReason, Game, GameConfig, GameOver, GameSnapshot, SpawnPolicy, TickOutcome};
+pub use crate::hamiltonian_matrix::{CycleGenerator, CycleProblem, HamiltonMatrix, CYCLE_NAMES};
+pub use crate::matrix::Matrix;
+pub use crate::neural::Network;
+pub use crate::point::Point;
+pub use crate::render::{HeadlessRenderer, Renderer};
+pub use crate::snake::Snake;
+pub use crate::topology::{Topology, TOPOLOGY_NAMES};
+pub use crate::strategy::{GameView, Strategy};
@@ -1,66 +1,255 @@
-mod direction;
-mod game;
-mod hamiltonian_matrix;
-mod matrix;
-mod point;
-mod snake;
-mod a_star;
-
-use crate::game::{Game, PathMode};
+mod cli;
+mod ncurses_renderer;
+
+use crate::cli::{ArenaOptions, CheckOptions, Command, OutputMode, PlayOptions, TrainOptions};
+use crate::ncurses_renderer::{screen_size, setup_ncurses, teardown_ncurses, NcursesRenderer};
+use rust_snake::bench::{print_comparison, run_arena_bench, run_bench};
+use rust_snake::evolve::evolve;
+use rust_snake::strategy::{self, TurnQueue};
+use rust_snake::{Arena, Direction, Game, GameConfig, GameError, HamiltonMatrix, HeadlessRenderer, Renderer, TickOutcome};
 use crossbeam::channel::{unbounded, TryRecvError};
-use ncurses::*;
+use ncurses::{getch, KEY_DOWN, KEY_F, KEY_LEFT, KEY_RIGHT, KEY_UP};
 use std::thread;
-use std::time::SystemTime;
+use std::time::{Duration, SystemTime};
 use backtrace::Backtrace;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
 use std::env;
+use std::fs;
 
-fn setup_ncurses() {
-    /* Setup ncurses. */
-    initscr();
-    raw();
+fn run_headless(mut game: Game) -> Result<(), GameError> {
+    let mut renderer = HeadlessRenderer;
 
-    /* Allow for extended keyboard (like F1). */
-    keypad(stdscr(), true);
-    noecho();
+    let over = loop {
+        renderer.draw(&game);
+        if let TickOutcome::Over(over) = game.step()? {
+            break over;
+        }
+    };
 
-    /* Invisible cursor. */
-    curs_set(CURSOR_VISIBILITY::CURSOR_INVISIBLE);
+    println!(
+        "Game over ({:?}) after {} steps, SnakeLen={}, Seed={}",
+        over.reason,
+        over.steps,
+        over.length,
+        game.seed()
+    );
+    Ok(())
 }
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
+fn run_arena_headless(mut arena: Arena) -> Result<(), GameError> {
+    let mut renderer = HeadlessRenderer;
+
+    while !arena.is_over() {
+        renderer.draw_arena(&arena);
+        arena.step()?;
+    }
+
+    println!("{}", arena.status_line());
+    Ok(())
+}
+
+/// Generates and validates cycles of every requested layout, printing what
+/// was wrong with any that fail. Returns whether all of them passed.
+fn run_check(options: &CheckOptions) -> bool {
+    let mut all_valid = true;
+    'layouts: for cycle in &options.cycles {
+        let mut valid = 0;
+        for i in 0..options.count {
+            let seed = options.seed.wrapping_add(i);
+            let mut rng = StdRng::seed_from_u64(seed);
+            let problems = match HamiltonMatrix::generate(*cycle, options.width, options.height, &mut rng) {
+                Ok(matrix) => match matrix.validate() {
+                    Ok(()) => {
+                        valid += 1;
+                        continue;
+                    }
+                    Err(problems) => problems,
+                },
+                Err(GameError::InvalidCycle(problems)) => problems,
+                Err(err) => {
+                    println!("Cycle={}: {}", cycle.name(), err);
+                    all_valid = false;
+                    continue 'layouts;
+                }
+            };
+
+            println!("Cycle={}, Seed={}: {} problems", cycle.name(), seed, problems.len());
+            for problem in problems.iter().take(10) {
+                println!("  {}", problem);
+            }
+        }
+
+        println!(
+            "Cycle={}, Board={}x{}: {}/{} valid",
+            cycle.name(),
+            options.width,
+            options.height,
+            valid,
+            options.count
+        );
+        all_valid &= valid == options.count;
+    }
+    all_valid
+}
+
+/// Evolves networks for the neural strategy, saving the fittest so far after
+/// every generation so an interrupted run keeps its progress.
+fn run_train(options: &TrainOptions) -> Result<(), GameError> {
+    let config = &options.evolve;
+    println!(
+        "Training Population={}, Generations={}, Hidden={:?}, Encoding={}, Actions={}, Board={}x{}, Seed={}",
+        config.population,
+        config.generations,
+        config.hidden,
+        config.encoding.name(),
+        config.actions.name(),
+        config.game.board_width,
+        config.game.board_height,
+        config.game.seed
+    );
+
+    evolve(config, |generation, fittest| {
+        println!(
+            "  Generation {:>4}: best {:.3}, mean {:.3}, best apples {:.2}",
+            generation.generation, generation.best_fitness, generation.mean_fitness, generation.best_apples
+        );
+        if let Err(err) = fs::write(&options.out, fittest.to_text()) {
+            eprintln!("Couldn't save {}: {}", options.out, err);
+            std::process::exit(1);
+        }
+    })?;
+
+    println!("Saved the fittest network to {}", options.out);
+    Ok(())
+}
+
+fn exit_with_error(err: &GameError) -> ! {
+    eprintln!("{}", err);
+    std::process::exit(1);
+}
+
+fn exit_with_usage(msg: &str) -> ! {
+    eprintln!("{}", msg);
+    eprintln!();
+    eprintln!("{}", cli::usage());
+    std::process::exit(2);
+}
+
+/// What the terminal loop plays: a single game or an arena.
+trait Session {
+    fn draw(&self, renderer: &mut NcursesRenderer);
+    fn advance(&mut self) -> Result<(), GameError>;
+    fn is_over(&self) -> bool;
+    fn running(&mut self) -> &mut bool;
+    fn tick_speed(&mut self) -> &mut u32;
+}
+
+impl Session for Game {
+    fn draw(&self, renderer: &mut NcursesRenderer) {
+        renderer.draw(self);
+    }
+
+    fn advance(&mut self) -> Result<(), GameError> {
+        self.step().map(|_| ())
+    }
+
+    fn is_over(&self) -> bool {
+        self.game_over().is_some()
+    }
+
+    fn running(&mut self) -> &mut bool {
+        &mut self.running
+    }
+
+    fn tick_speed(&mut self) -> &mut u32 {
+        &mut self.tick_speed
+    }
+}
+
+impl Session for Arena {
+    fn draw(&self, renderer: &mut NcursesRenderer) {
+        renderer.draw_arena(self);
+    }
+
+    fn advance(&mut self) -> Result<(), GameError> {
+        self.step()
+    }
+
+    fn is_over(&self) -> bool {
+        Arena::is_over(self)
+    }
+
+    fn running(&mut self) -> &mut bool {
+        &mut self.running
+    }
+
+    fn tick_speed(&mut self) -> &mut u32 {
+        &mut self.tick_speed
+    }
+}
+
+/// Brings up ncurses and sizes the board to the terminal unless the options
+/// gave a size, exiting if it doesn't fit.
+fn setup_tui(options: &PlayOptions) -> GameConfig {
     setup_ncurses();
 
-    std::panic::set_hook(Box::new(|pl| {
-        endwin();
+    std::panic::set_hook(Box::new(|_pl| {
+        teardown_ncurses();
         println!("{:?}", Backtrace::new());
     }));
 
-    let mut max_x = 0;
-    let mut max_y = 0;
-    getmaxyx(stdscr(), &mut max_y, &mut max_x);
+    let (max_x, max_y) = screen_size();
 
-    if max_x % 2 == 0 {
-        max_x -= 1;
+    // leave room for the HUD line and the border
+    let mut fit_width = (max_x - 2).max(0) as usize;
+    let fit_height = (max_y - 3).max(0) as usize;
+    if fit_width % 2 != 0 && fit_height % 2 != 0 {
+        fit_width -= 1;
     }
 
-    if max_y % 2 != 0 {
-        max_y -= 1;
-    }
+    let mut config = options.game.clone();
+    config.board_width = options.width.unwrap_or(fit_width);
+    config.board_height = options.height.unwrap_or(fit_height);
 
-    let mut path_mode = PathMode::HAMILTON;
-    if let Some(pathn) = args.get(1) {
-        match pathn.as_ref() {
-            "astar" => { path_mode = PathMode::ASTAR },
-            "hamilton" => { path_mode = PathMode::HAMILTON },
-            _ => {
-                panic!("Unexpected Pathing Mode {}", pathn);
-            }
+    let check = cli::check_game(&config).and_then(|_| {
+        if config.board_width > fit_width || config.board_height > fit_height {
+            return Err(format!(
+                "Board {}x{} does not fit the terminal, which fits at most {}x{}",
+                config.board_width, config.board_height, fit_width, fit_height
+            ));
         }
+        Ok(())
+    });
+    if let Err(msg) = check {
+        teardown_ncurses();
+        exit_with_usage(&msg);
     }
+    config
+}
 
-    let mut game = Game::new(1, 2, max_x-2, max_y-2, path_mode);
-    // let mut game = Game::new(1, 2, 19 - 2, 16 - 2, PathMode::HAMILTON);
+/// Direction an arrow key or one of WASD steers in.
+fn key_turn(ch: i32) -> Option<Direction> {
+    if ch == KEY_UP || ch == 'w' as i32 {
+        return Some(Direction::UP);
+    }
+    if ch == KEY_DOWN || ch == 's' as i32 {
+        return Some(Direction::DOWN);
+    }
+    if ch == KEY_LEFT || ch == 'a' as i32 {
+        return Some(Direction::LEFT);
+    }
+    if ch == KEY_RIGHT || ch == 'd' as i32 {
+        return Some(Direction::RIGHT);
+    }
+    None
+}
+
+/// Draws and steps `session` until F1 is pressed, then shuts ncurses down.
+/// With `turns` a person is steering, so the steering keys feed it and the
+/// speed moves to `-` and `+`.
+fn run_tui<S: Session>(session: &mut S, paused: bool, turns: Option<&TurnQueue>) {
+    let mut renderer = NcursesRenderer::new(1, 2);
 
     let (trx, rev) = unbounded();
 
@@ -72,7 +261,8 @@ fn main() {
     });
 
     let mut running = true;
-    game.running = false;
+    let mut game_error = None;
+    *session.running() = !paused;
     let mut last_tick = SystemTime::now();
 
     while running {
@@ -81,15 +271,14 @@ fn main() {
             .unwrap()
             .as_millis();
 
-        if current_mills > game.tick_speed as u128
+        if current_mills > *session.tick_speed() as u128
         {
-            game.draw();
-
-            if game.running {
-                game.move_snake();
+            session.draw(&mut renderer);
 
-                if !game.tick() {
-                    game.running = false;
+            if *session.running() {
+                if let Err(err) = session.advance() {
+                    game_error = Some(err);
+                    *session.running() = false;
                 }
             }
 
@@ -97,7 +286,7 @@ fn main() {
         }
 
         if rev.is_empty() {
-            thread::sleep_ms(1);
+            thread::sleep(Duration::from_millis(1));
             continue;
         }
 
@@ -107,34 +296,139 @@ fn main() {
                     running = false;
                 }
 
-                if ch == ' ' as i32 {
-                    game.running = !game.running;
+                if ch == ' ' as i32 && !session.is_over() {
+                    *session.running() = !*session.running();
                 }
 
-                if ch == 'w' as i32 {
-                    game.tick_speed += 1;
+                if let (Some(turns), Some(dir)) = (turns, key_turn(ch)) {
+                    turns.push(dir);
+                    continue;
                 }
 
-                if ch == 's' as i32 {
-                    game.tick_speed -= 1;
+                if ch == 'w' as i32 || ch == '-' as i32 {
+                    *session.tick_speed() += 1;
                 }
-            }
 
-            Err(TryRecvError::Empty) => {
-                println!("{}", "empty");
-                running = false;
+                if ch == 's' as i32 || ch == '+' as i32 {
+                    *session.tick_speed() = session.tick_speed().saturating_sub(1);
+                }
             }
 
+            Err(TryRecvError::Empty) => {}
+
             Err(TryRecvError::Disconnected) => {
-                println!("{}", "disconnected");
                 running = false;
             }
         }
     }
 
-    endwin();
+    teardown_ncurses();
+
+    if let Some(err) = game_error {
+        exit_with_error(&err);
+    }
+}
+
+fn run_arena(options: &ArenaOptions) {
+    let strategies = options
+        .strategies
+        .iter()
+        .map(|name| strategy::build(name, &options.play.strategy_options))
+        .collect::<Result<Vec<_>, GameError>>()
+        .unwrap_or_else(|err| exit_with_error(&err));
+    if options.play.output == OutputMode::Headless {
+        let result = Arena::new(&options.play.game, strategies).and_then(run_arena_headless);
+        if let Err(err) = result {
+            exit_with_error(&err);
+        }
+        return;
+    }
+
+    let config = setup_tui(&options.play);
+    let mut arena = match Arena::new(&config, strategies) {
+        Ok(arena) => arena,
+        Err(err) => {
+            teardown_ncurses();
+            exit_with_error(&err);
+        }
+    };
+    let human = options.strategies.iter().any(|name| name == "human");
+    run_tui(
+        &mut arena,
+        options.play.paused,
+        Some(&options.play.strategy_options.turns).filter(|_| human),
+    );
 }
 
-// fn main() {
-//     let mut game = Game::new(1, 2, 19 - 2, 16 - 2);
-// }
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let options = match cli::parse_args(&args) {
+        Ok(Command::Play(options)) => options,
+        Ok(Command::Bench(configs)) => {
+            let mut reports = vec![];
+            for config in &configs {
+                match run_bench(config) {
+                    Ok(report) => {
+                        report.print();
+                        reports.push(report);
+                    }
+                    Err(err) => exit_with_error(&err),
+                }
+            }
+            if reports.len() > 1 {
+                print_comparison(&reports);
+            }
+            return;
+        }
+        Ok(Command::Arena(options)) => {
+            run_arena(&options);
+            return;
+        }
+        Ok(Command::ArenaBench(config)) => {
+            match run_arena_bench(&config) {
+                Ok(report) => report.print(),
+                Err(err) => exit_with_error(&err),
+            }
+            return;
+        }
+        Ok(Command::Check(options)) => {
+            if !run_check(&options) {
+                std::process::exit(1);
+            }
+            return;
+        }
+        Ok(Command::Train(options)) => {
+            if let Err(err) = run_train(&options) {
+                exit_with_error(&err);
+            }
+            return;
+        }
+        Ok(Command::Help) => {
+            println!("{}", cli::usage());
+            return;
+        }
+        Err(msg) => exit_with_usage(&msg),
+    };
+
+    let strategy = strategy::build(&options.strategy, &options.strategy_options)
+        .unwrap_or_else(|err| exit_with_error(&err));
+    if options.output == OutputMode::Headless {
+        let result = Game::new(&options.game, strategy).and_then(run_headless);
+        if let Err(err) = result {
+            exit_with_error(&err);
+        }
+        return;
+    }
+
+    let config = setup_tui(&options);
+    let mut game = match Game::new(&config, strategy) {
+        Ok(game) => game,
+        Err(err) => {
+            teardown_ncurses();
+            exit_with_error(&err);
+        }
+    };
+    let human = options.strategy == "human";
+    run_tui(&mut game, options.paused, Some(&options.strategy_options.turns).filter(|_| human));
+}
@@ -1,138 +1,178 @@
+#![allow(clippy::needless_return, clippy::redundant_field_names)]
+
+use crate::direction::Direction;
 use crate::point::Point;
 
+use std::ops::{Index, IndexMut};
+
+/// A `width` by `height` grid of values, stored row by row in one buffer.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Matrix<T> {
-    data: Vec<Vec<Option<T>>>,
+    data: Vec<T>,
     width: usize,
     height: usize,
 }
 
-impl<T> Matrix<T> {
+impl<T: Clone + Default> Matrix<T> {
+    /// A matrix with every cell set to `T::default()`.
     pub fn new(width: usize, height: usize) -> Matrix<T> {
-        let mut data_arr1 = Vec::with_capacity(width);
-
-        for _ in 0..width {
-            let mut data_arr2 = Vec::with_capacity(height);
+        return Matrix::filled(width, height, T::default());
+    }
+}
 
-            for _ in 0..height {
-                data_arr2.push(None);
-            }
+impl<T: Clone> Matrix<T> {
+    pub fn filled(width: usize, height: usize, value: T) -> Matrix<T> {
+        return Matrix {
+            data: vec![value; width * height],
+            width: width,
+            height: height,
+        };
+    }
 
-            data_arr1.push(data_arr2);
+    pub fn fill(&mut self, value: T) {
+        for cell in self.data.iter_mut() {
+            *cell = value.clone();
         }
+    }
+}
 
+impl<T> Matrix<T> {
+    /// A matrix with each cell set to `f` of its coordinates.
+    pub fn from_fn<F: FnMut(Point) -> T>(width: usize, height: usize, mut f: F) -> Matrix<T> {
+        let mut data = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                data.push(f(Point::new(x as i32, y as i32)));
+            }
+        }
         return Matrix {
-            data: data_arr1,
+            data: data,
             width: width,
-            height: height
+            height: height,
         };
     }
 
-    pub fn set<V: num::ToPrimitive>(&mut self, x: V, y: V, v: T) {
-        self.data[x.to_usize().unwrap()][y.to_usize().unwrap()] = Some(v);
+    fn offset(&self, pt: &Point) -> Option<usize> {
+        if !self.contains(pt) {
+            return None;
+        }
+        return Some(pt.y as usize * self.width + pt.x as usize);
     }
 
-    pub fn set_pt(&mut self, pt: &Point, v: T) {
-        self.set(pt.x, pt.y, v);
+    pub fn contains(&self, pt: &Point) -> bool {
+        pt.x >= 0 && pt.y >= 0 && (pt.x as usize) < self.width && (pt.y as usize) < self.height
     }
 
-    pub fn unset<V: num::ToPrimitive>(&mut self, x: V, y: V) {
-        self.data[x.to_usize().unwrap()][y.to_usize().unwrap()] = None;
+    /// The value at `pt`, or `None` if it's off the grid.
+    pub fn get(&self, pt: &Point) -> Option<&T> {
+        let offset = self.offset(pt)?;
+        return self.data.get(offset);
     }
 
-    pub fn unset_pt(&mut self, pt: &Point) {
-        self.unset(pt.x, pt.y);
+    pub fn get_mut(&mut self, pt: &Point) -> Option<&mut T> {
+        let offset = self.offset(pt)?;
+        return self.data.get_mut(offset);
     }
 
-    pub fn get<V: num::ToPrimitive>(&self, x: V, y: V) -> Option<&T> {
-        let x = x.to_usize();
-        if x.is_none() {
-            return None;
-        }
-        let x = x.unwrap();
-
-        let y = y.to_usize();
-        if y.is_none() {
-            return None;
-        }
-        let y = y.unwrap();
+    pub fn get_width(&self) -> usize {
+        self.width
+    }
 
-        let arr1 = self.data.get(x);
-        if arr1.is_none() {
-            return None;
-        }
+    pub fn get_height(&self) -> usize {
+        self.height
+    }
 
-        let elt = arr1.unwrap().get(y);
-        if elt.is_none() {
-            return None;
-        }
+    /// Every value, row by row.
+    pub fn cells(&self) -> impl Iterator<Item = &T> {
+        self.data.iter()
+    }
 
-        let elt = elt.unwrap();
-        if elt.is_none() {
-            return None;
-        }
+    /// Every coordinate on the grid, row by row.
+    pub fn points(&self) -> impl Iterator<Item = Point> {
+        let width = self.width;
+        (0..self.data.len()).map(move |i| Point::new((i % width) as i32, (i / width) as i32))
+    }
 
-        return elt.as_ref();
+    /// Every coordinate with its value, row by row.
+    pub fn enumerate(&self) -> impl Iterator<Item = (Point, &T)> {
+        self.points().zip(self.data.iter())
     }
 
-    pub fn get_mut<V: num::ToPrimitive>(&mut self, x: V, y: V) -> Option<&mut T> {
-        let x = x.to_usize();
-        if x.is_none() {
-            return None;
-        }
-        let x = x.unwrap();
+    /// The up to four cells next to `pt` that are on the grid.
+    pub fn neighbours(&self, pt: Point) -> impl Iterator<Item = Point> + '_ {
+        Direction::all()
+            .iter()
+            .map(move |dir| pt.dir_adj(dir))
+            .filter(move |next| self.contains(next))
+    }
+}
 
-        let y = y.to_usize();
-        if y.is_none() {
-            return None;
-        }
-        let y = y.unwrap();
+impl<T> Index<Point> for Matrix<T> {
+    type Output = T;
 
-        let arr1 = self.data.get_mut(x);
-        if arr1.is_none() {
-            return None;
+    fn index(&self, pt: Point) -> &T {
+        match self.offset(&pt) {
+            Some(offset) => &self.data[offset],
+            None => panic!("{:?} is off a {}x{} matrix", pt, self.width, self.height),
         }
+    }
+}
 
-        let elt = arr1.unwrap().get_mut(y);
-        if elt.is_none() {
-            return None;
+impl<T> IndexMut<Point> for Matrix<T> {
+    fn index_mut(&mut self, pt: Point) -> &mut T {
+        match self.offset(&pt) {
+            Some(offset) => &mut self.data[offset],
+            None => panic!("{:?} is off a {}x{} matrix", pt, self.width, self.height),
         }
+    }
+}
 
-        let elt = elt.unwrap();
-        if elt.is_none() {
-            return None;
+impl<T: std::fmt::Display> Matrix<T> {
+    pub fn print_matrix(&self) {
+        for y in 0..self.height {
+            for x in 0..self.width {
+                print!("{:04} ", self[Point::new(x as i32, y as i32)]);
+            }
+            println!();
         }
-
-        return elt.as_mut();
     }
+}
 
-    pub fn get_pt(&self, pt: &Point) -> Option<&T> {
-        self.get(pt.x, pt.y)
-    }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::topology::Topology;
 
-    pub fn get_mut_pt(&mut self, pt: &Point) -> Option<&mut T> {
-        self.get_mut(pt.x, pt.y)
+    fn sorted(points: impl Iterator<Item = Point>) -> Vec<(i32, i32)> {
+        let mut points: Vec<(i32, i32)> = points.map(|pt| (pt.x, pt.y)).collect();
+        points.sort();
+        points
     }
 
-    pub fn get_width(&self) -> usize {
-        self.width
+    #[test]
+    fn neighbours_stay_on_the_grid() {
+        let matrix: Matrix<u8> = Matrix::new(4, 3);
+        assert_eq!(sorted(matrix.neighbours(Point::new(0, 0))), vec![(0, 1), (1, 0)]);
+        assert_eq!(sorted(matrix.neighbours(Point::new(3, 2))), vec![(2, 2), (3, 1)]);
+        assert_eq!(sorted(matrix.neighbours(Point::new(2, 0))), vec![(1, 0), (2, 1), (3, 0)]);
+        assert_eq!(sorted(matrix.neighbours(Point::new(1, 1))), vec![(0, 1), (1, 0), (1, 2), (2, 1)]);
     }
 
-    pub fn get_height(&self) -> usize {
-        self.height
+    #[test]
+    fn neighbours_wrap_on_a_torus() {
+        let matrix: Matrix<u8> = Matrix::new(4, 3);
+        let neighbours = |pt| sorted(Topology::Torus.neighbours(pt, matrix.get_width(), matrix.get_height()));
+        assert_eq!(neighbours(Point::new(0, 0)), vec![(0, 1), (0, 2), (1, 0), (3, 0)]);
+        assert_eq!(neighbours(Point::new(3, 2)), vec![(0, 2), (2, 2), (3, 0), (3, 1)]);
+        assert_eq!(neighbours(Point::new(1, 1)), vec![(0, 1), (1, 0), (1, 2), (2, 1)]);
     }
-}
 
-impl<T: std::fmt::Display> Matrix<T> {
-    pub fn print_matrix(&self) {
-        for x in 0..self.width {
-            for y in 0..self.height {
-                if let Some(v) = self.get(x, y) {
-                    print!("{:04} ", v);
-                } else {
-                    print!(" --  ");
-                }
-            }
-            println!();
-        }
+    #[test]
+    fn off_grid_points_have_no_value() {
+        let matrix = Matrix::from_fn(4, 3, |pt| pt.y * 4 + pt.x);
+        assert_eq!(matrix.get(&Point::new(3, 2)), Some(&11));
+        assert_eq!(matrix.get(&Point::new(4, 0)), None);
+        assert_eq!(matrix.get(&Point::new(0, -1)), None);
+        assert_eq!(matrix.cells().cloned().collect::<Vec<_>>(), (0..12).collect::<Vec<_>>());
     }
-}
\ No newline at end of file
+}
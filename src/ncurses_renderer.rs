@@ -0,0 +1,155 @@
+use rust_snake::{Arena, Direction, Game, Matrix, Point, Renderer, Snake};
+use ncurses::*;
+
+pub fn setup_ncurses() {
+    /* Setup ncurses. */
+    initscr();
+    raw();
+
+    /* Allow for extended keyboard (like F1). */
+    keypad(stdscr(), true);
+    noecho();
+
+    /* Invisible cursor. */
+    curs_set(CURSOR_VISIBILITY::CURSOR_INVISIBLE);
+
+    /* One colour pair per arena snake, numbered from 1. */
+    if has_colors() {
+        start_color();
+        for (i, colour) in SNAKE_COLOURS.iter().enumerate() {
+            init_pair(i as i16 + 1, *colour, COLOR_BLACK);
+        }
+    }
+}
+
+/// Colours arena snakes are drawn in, going round again past the last.
+const SNAKE_COLOURS: [i16; 6] = [COLOR_GREEN, COLOR_CYAN, COLOR_YELLOW, COLOR_MAGENTA, COLOR_RED, COLOR_BLUE];
+
+fn snake_colour(i: usize) -> attr_t {
+    COLOR_PAIR((i % SNAKE_COLOURS.len()) as i16 + 1)
+}
+
+pub fn teardown_ncurses() {
+    endwin();
+}
+
+pub fn screen_size() -> (i32, i32) {
+    let mut max_x = 0;
+    let mut max_y = 0;
+    getmaxyx(stdscr(), &mut max_y, &mut max_x);
+    (max_x, max_y)
+}
+
+fn get_snake_seg(curr: &Direction, next: Option<&Direction>) -> chtype {
+    match curr {
+        Direction::UP => match next {
+            Some(Direction::RIGHT) => ACS_ULCORNER(),
+            Some(Direction::LEFT) => ACS_URCORNER(),
+            _ => ACS_VLINE(),
+        },
+        Direction::RIGHT => match next {
+            Some(Direction::UP) => ACS_LRCORNER(),
+            Some(Direction::DOWN) => ACS_URCORNER(),
+            _ => ACS_HLINE(),
+        },
+        Direction::DOWN => match next {
+            Some(Direction::RIGHT) => ACS_LLCORNER(),
+            Some(Direction::LEFT) => ACS_LRCORNER(),
+            _ => ACS_VLINE(),
+        },
+        Direction::LEFT => match next {
+            Some(Direction::UP) => ACS_LLCORNER(),
+            Some(Direction::DOWN) => ACS_ULCORNER(),
+            _ => ACS_HLINE(),
+        },
+    }
+}
+
+/// Draws the game onto the terminal, with the board's top left cell at
+/// (`min_x`, `min_y`) on screen.
+pub struct NcursesRenderer {
+    min_x: i32,
+    min_y: i32,
+}
+
+impl NcursesRenderer {
+    pub fn new(min_x: i32, min_y: i32) -> NcursesRenderer {
+        NcursesRenderer {
+            min_x,
+            min_y,
+        }
+    }
+
+    fn draw_border(&self, width: i32, height: i32) {
+        let max_x = self.min_x + width - 1;
+        let max_y = self.min_y + height - 1;
+
+        mvhline(self.min_y - 1, self.min_x - 1, ACS_HLINE(), width + 2);
+        mvhline(max_y + 1, self.min_x - 1, ACS_HLINE(), width + 2);
+
+        mvvline(self.min_y - 1, self.min_x - 1, ACS_VLINE(), height + 2);
+        mvvline(self.min_y - 1, max_x + 1, ACS_VLINE(), height + 2);
+
+        mvhline(self.min_y - 1, self.min_x - 1, ACS_ULCORNER(), 1);
+        mvhline(max_y + 1, self.min_x - 1, ACS_LLCORNER(), 1);
+        mvhline(max_y + 1, max_x + 1, ACS_LRCORNER(), 1);
+        mvhline(self.min_y - 1, max_x + 1, ACS_URCORNER(), 1);
+    }
+
+    fn draw_snake(&self, snake: &Snake) {
+        snake.for_each_segment(|pt, i| {
+            if i == 0 {
+                mvhline(self.min_y + pt.y, self.min_x + pt.x, ACS_BLOCK(), 1);
+                return;
+            }
+
+            let prev_dir = snake.segment_dir(i - 1).unwrap();
+            let to_print = get_snake_seg(&prev_dir, snake.segment_dir(i).as_ref());
+            mvhline(self.min_y + pt.y, self.min_x + pt.x, to_print, 1);
+        });
+    }
+
+    fn draw_board(&self, walls: &Matrix<bool>, apples: &[Point]) {
+        self.draw_border(walls.get_width() as i32, walls.get_height() as i32);
+
+        for (pt, wall) in walls.enumerate() {
+            if *wall {
+                mvhline(self.min_y + pt.y, self.min_x + pt.x, '#' as chtype, 1);
+            }
+        }
+        for apple in apples {
+            mvhline(self.min_y + apple.y, self.min_x + apple.x, ACS_CKBOARD(), 1);
+        }
+    }
+}
+
+impl Renderer for NcursesRenderer {
+    fn draw(&mut self, game: &Game) {
+        erase();
+        mvprintw(0, 0, &game.status_line());
+        self.draw_board(game.walls(), game.apples());
+        self.draw_snake(game.snake());
+        refresh();
+    }
+
+    fn draw_arena(&mut self, arena: &Arena) {
+        erase();
+        let header = arena.header();
+        mvprintw(0, 0, &header);
+        self.draw_board(arena.walls(), arena.apples());
+
+        // each snake and its score in the HUD share a colour
+        let mut x = header.len() as i32;
+        for (i, entrant) in arena.entrants().iter().enumerate() {
+            let label = format!(", {}:{}", i + 1, entrant.status());
+            attron(snake_colour(i));
+            mvprintw(0, x, &label);
+            if entrant.is_alive() {
+                self.draw_snake(entrant.snake());
+            }
+            attroff(snake_colour(i));
+            x += label.len() as i32;
+        }
+        refresh();
+    }
+}
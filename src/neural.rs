@@ -0,0 +1,199 @@
+use crate::encode::Encoding;
+use crate::env::ActionSpace;
+use crate::error::GameError;
+use rand::Rng;
+
+/// A small fully connected feed-forward network mapping an encoding of the
+/// board to an action. Hidden layers use tanh; the action is whichever
+/// output comes out highest. Its weights are the genome the trainer evolves.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Network {
+    /// Width of every layer, inputs first and actions last.
+    layers: Vec<usize>,
+    encoding: Encoding,
+    actions: ActionSpace,
+    /// Each layer in turn, each of its outputs in turn, as that output's
+    /// input weights followed by its bias.
+    weights: Vec<f32>,
+}
+
+impl Network {
+    /// Number of weights a network with these layer widths has.
+    pub fn weight_count(layers: &[usize]) -> usize {
+        layers.windows(2).map(|pair| (pair[0] + 1) * pair[1]).sum()
+    }
+
+    pub fn new(
+        layers: Vec<usize>,
+        encoding: Encoding,
+        actions: ActionSpace,
+        weights: Vec<f32>,
+    ) -> Result<Network, GameError> {
+        if layers.len() < 2 || layers.contains(&0) {
+            return Err(GameError::InvalidGenome(format!("bad layer widths {:?}", layers)));
+        }
+        if layers[layers.len() - 1] != actions.size() {
+            return Err(GameError::InvalidGenome(format!(
+                "{} outputs for {} {} actions",
+                layers[layers.len() - 1],
+                actions.size(),
+                actions.name()
+            )));
+        }
+        if weights.len() != Network::weight_count(&layers) {
+            return Err(GameError::InvalidGenome(format!(
+                "{} weights where layers {:?} take {}",
+                weights.len(),
+                layers,
+                Network::weight_count(&layers)
+            )));
+        }
+        Ok(Network {
+            layers,
+            encoding,
+            actions,
+            weights,
+        })
+    }
+
+    /// A network taking `inputs` features through `hidden` layers to the
+    /// actions, with weights drawn uniformly from -1 to 1.
+    pub fn random<R: Rng>(
+        inputs: usize,
+        hidden: &[usize],
+        encoding: Encoding,
+        actions: ActionSpace,
+        rng: &mut R,
+    ) -> Result<Network, GameError> {
+        let mut layers = vec![inputs];
+        layers.extend_from_slice(hidden);
+        layers.push(actions.size());
+        let weights = (0..Network::weight_count(&layers))
+            .map(|_| rng.gen_range(-1.0, 1.0))
+            .collect();
+        Network::new(layers, encoding, actions, weights)
+    }
+
+    /// The same network with other weights, which must be as many.
+    pub fn with_weights(&self, weights: Vec<f32>) -> Network {
+        assert_eq!(weights.len(), self.weights.len());
+        Network {
+            layers: self.layers.clone(),
+            encoding: self.encoding,
+            actions: self.actions,
+            weights,
+        }
+    }
+
+    pub fn layers(&self) -> &[usize] {
+        &self.layers
+    }
+
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    pub fn actions(&self) -> ActionSpace {
+        self.actions
+    }
+
+    pub fn weights(&self) -> &[f32] {
+        &self.weights
+    }
+
+    /// Number of features the network expects.
+    pub fn inputs(&self) -> usize {
+        self.layers[0]
+    }
+
+    /// Runs `inputs` through the network and returns the output layer.
+    pub fn forward(&self, inputs: &[f32]) -> Vec<f32> {
+        let mut values = inputs.to_vec();
+        let mut weights = self.weights.iter();
+        let last = self.layers.len() - 2;
+        for (i, pair) in self.layers.windows(2).enumerate() {
+            let mut next = Vec::with_capacity(pair[1]);
+            for _ in 0..pair[1] {
+                let mut sum = 0.0;
+                for value in &values {
+                    sum += value * weights.next().unwrap();
+                }
+                sum += weights.next().unwrap();
+                next.push(if i == last { sum } else { sum.tanh() });
+            }
+            values = next;
+        }
+        values
+    }
+
+    /// The action with the highest output for `inputs`.
+    pub fn action(&self, inputs: &[f32]) -> usize {
+        let outputs = self.forward(inputs);
+        let mut best = 0;
+        for (i, output) in outputs.iter().enumerate() {
+            if *output > outputs[best] {
+                best = i;
+            }
+        }
+        best
+    }
+
+    /// Reads a network written by `to_text`.
+    pub fn parse(text: &str) -> Result<Network, GameError> {
+        let mut layers = None;
+        let mut encoding = None;
+        let mut actions = None;
+        let mut weights = None;
+
+        for line in text.lines().map(|line| line.trim()) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut words = line.split_whitespace();
+            let key = words.next().unwrap_or("");
+            let values: Vec<&str> = words.collect();
+            let bad = |what: &str| GameError::InvalidGenome(format!("bad {} line: {}", what, line));
+            match key {
+                "layers" => {
+                    let widths = values.iter().map(|value| value.parse::<usize>());
+                    layers = Some(widths.collect::<Result<Vec<usize>, _>>().map_err(|_| bad(key))?);
+                }
+                "encoding" => {
+                    let name = values.first().copied().unwrap_or("");
+                    encoding = Some(Encoding::from_name(name).ok_or_else(|| bad(key))?);
+                }
+                "actions" => {
+                    let name = values.first().copied().unwrap_or("");
+                    actions = Some(ActionSpace::from_name(name).ok_or_else(|| bad(key))?);
+                }
+                "weights" => {
+                    let parsed = values.iter().map(|value| value.parse::<f32>());
+                    weights = Some(parsed.collect::<Result<Vec<f32>, _>>().map_err(|_| bad(key))?);
+                }
+                _ => return Err(GameError::InvalidGenome(format!("unexpected line: {}", line))),
+            }
+        }
+
+        let missing = |what: &str| GameError::InvalidGenome(format!("no {} line", what));
+        Network::new(
+            layers.ok_or_else(|| missing("layers"))?,
+            encoding.ok_or_else(|| missing("encoding"))?,
+            actions.ok_or_else(|| missing("actions"))?,
+            weights.ok_or_else(|| missing("weights"))?,
+        )
+    }
+
+    /// The network as text for `parse`, one line each for the layer widths,
+    /// the encoding, the action space and the weights.
+    pub fn to_text(&self) -> String {
+        let layers: Vec<String> = self.layers.iter().map(|width| width.to_string()).collect();
+        let weights: Vec<String> = self.weights.iter().map(|weight| weight.to_string()).collect();
+        format!(
+            "# rust_snake neural network\nlayers {}\nencoding {}\nactions {}\nweights {}\n",
+            layers.join(" "),
+            self.encoding.name(),
+            self.actions.name(),
+            weights.join(" ")
+        )
+    }
+}
@@ -1,3 +1,5 @@
+#![allow(clippy::needless_return, clippy::redundant_field_names)]
+
 use crate::direction::Direction;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -27,6 +29,11 @@ impl Point {
         }
     }
 
+    /// Steps between the two points along the grid.
+    pub fn manhattan(&self, other: &Point) -> u32 {
+        ((self.x - other.x).abs() + (self.y - other.y).abs()) as u32
+    }
+
     pub fn in_dir(&self, other: &Point) -> Direction {
         if other.x == self.x {
             if other.y < self.y {
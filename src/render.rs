@@ -0,0 +1,20 @@
+use crate::arena::Arena;
+use crate::game::Game;
+
+/// Something that can present the state of a game. The game loop calls
+/// `draw` once per frame; the game itself never touches the terminal.
+pub trait Renderer {
+    fn draw(&mut self, game: &Game);
+
+    /// Same as `draw`, for an arena of several snakes.
+    fn draw_arena(&mut self, arena: &Arena);
+}
+
+/// Renderer that discards every frame, used when running without a terminal.
+pub struct HeadlessRenderer;
+
+impl Renderer for HeadlessRenderer {
+    fn draw(&mut self, _game: &Game) {}
+
+    fn draw_arena(&mut self, _arena: &Arena) {}
+}
@@ -1,124 +1,130 @@
+#![allow(clippy::needless_return, clippy::redundant_field_names)]
+
 use crate::direction::Direction;
+use crate::matrix::Matrix;
 use crate::point::Point;
+use crate::topology::Topology;
 
-pub struct Snake {
-    segment_dirs: Vec<Direction>,
-    head: Point,
-}
+use std::collections::VecDeque;
 
-fn get_snake_seg(curr: &Direction, next: Option<&Direction>) -> u64 {
-    match curr {
-        Direction::UP => match next {
-            Some(Direction::RIGHT) => return ncurses::ACS_ULCORNER(),
-            Some(Direction::LEFT) => return ncurses::ACS_URCORNER(),
-            _ => return ncurses::ACS_VLINE(),
-        },
-        Direction::RIGHT => match next {
-            Some(Direction::UP) => return ncurses::ACS_LRCORNER(),
-            Some(Direction::DOWN) => return ncurses::ACS_URCORNER(),
-            _ => return ncurses::ACS_HLINE(),
-        },
-        Direction::DOWN => match next {
-            Some(Direction::RIGHT) => return ncurses::ACS_LLCORNER(),
-            Some(Direction::LEFT) => return ncurses::ACS_LRCORNER(),
-            _ => return ncurses::ACS_VLINE(),
-        },
-        Direction::LEFT => match next {
-            Some(Direction::UP) => return ncurses::ACS_LLCORNER(),
-            Some(Direction::DOWN) => return ncurses::ACS_ULCORNER(),
-            _ => return ncurses::ACS_HLINE(),
-        },
-    };
+/// The snake's cells from head to tail, with a grid counting how many
+/// segments sit on each cell so membership and collision checks don't walk
+/// the body.
+#[derive(Clone)]
+pub struct Snake {
+    cells: VecDeque<Point>,
+    occupied: Matrix<u8>,
+    /// Moves left for which the tail stays put.
+    growth: usize,
+    topology: Topology,
 }
 
 impl Snake {
-    pub fn new(head: Point) -> Snake {
-        Snake {
-            segment_dirs: vec![Direction::LEFT; 5],
-            head: head,
+    /// A snake with its head at `head` and `length` segments trailing off to
+    /// the left, on a `width` by `height` board.
+    pub fn new(head: Point, length: usize, width: usize, height: usize, topology: Topology) -> Snake {
+        let mut snake = Snake {
+            cells: VecDeque::with_capacity(length + 1),
+            occupied: Matrix::new(width, height),
+            growth: 0,
+            topology: topology,
+        };
+        for i in (0..=length).rev() {
+            snake.push_head(head.add(-(i as i32), 0));
         }
+        return snake;
     }
 
-    pub fn on_snake(&self, pt: Point) -> bool {
-        let mut on = false;
-        self.for_each_segment(|other_pt, _| {
-            if other_pt == pt {
-                on = true;
-            }
-        });
-        return on;
+    fn push_head(&mut self, pt: Point) {
+        if let Some(count) = self.occupied.get_mut(&pt) {
+            *count += 1;
+        }
+        self.cells.push_front(pt);
     }
 
-    pub fn for_each_segment<F: FnMut(Point, usize)>(&self, mut f: F) {
-        let mut current_seg = self.head;
-        f(current_seg, 0);
-        for i in 0..self.segment_dirs.len() {
-            current_seg = match self.segment_dirs[i] {
-                Direction::UP => current_seg.add(0, -1),
-                Direction::RIGHT => current_seg.add(1, 0),
-                Direction::DOWN => current_seg.add(0, 1),
-                Direction::LEFT => current_seg.add(-1, 0),
-            };
-
-            f(current_seg, i + 1);
+    fn pop_tail(&mut self) {
+        if let Some(pt) = self.cells.pop_back() {
+            if let Some(count) = self.occupied.get_mut(&pt) {
+                *count -= 1;
+            }
         }
     }
 
-    pub fn draw(&self, min_x: i32, min_y: i32) {
-        self.for_each_segment(|pt, i| {
-            if i == 0 {
-                ncurses::mvhline(min_y + pt.y, min_x + pt.x, ncurses::ACS_BLOCK(), 1);
-                return;
-            }
+    pub fn on_snake(&self, pt: Point) -> bool {
+        return self.occupied.get(&pt).is_some_and(|count| *count > 0);
+    }
 
-            let next_dir = self.segment_dirs.get(i);
-            let to_print = get_snake_seg(&self.segment_dirs[i - 1], next_dir);
-            ncurses::mvhline(min_y + pt.y, min_x + pt.x, to_print, 1);
-        });
+    pub fn for_each_segment<F: FnMut(Point, usize)>(&self, mut f: F) {
+        for (i, pt) in self.cells.iter().enumerate() {
+            f(*pt, i);
+        }
     }
 
+    /// True if the head shares its cell with another segment.
     pub fn check_collide(&self) -> bool {
-        let mut dead = false;
-        self.for_each_segment(|pt, i| {
-            if i != 0 && pt == self.head {
-                dead = true;
-            }
-        });
-        return dead;
+        return self.occupied.get(self.get_head()).is_some_and(|count| *count > 1);
     }
 
     pub fn move_dir(&mut self, dir: &Direction) {
-        self.segment_dirs.insert(0, dir.oposite());
-        self.segment_dirs.pop();
-
-        self.head = match dir {
-            Direction::UP => self.head.add(0, -1),
-            Direction::RIGHT => self.head.add(1, 0),
-            Direction::DOWN => self.head.add(0, 1),
-            Direction::LEFT => self.head.add(-1, 0),
-        };
+        let head = self.topology.step(
+            self.get_head(),
+            dir,
+            self.occupied.get_width(),
+            self.occupied.get_height(),
+        );
+        self.push_head(head);
+        if self.growth > 0 {
+            self.growth -= 1;
+        } else {
+            self.pop_tail();
+        }
+    }
+
+    /// Direction from segment `i` to the one after it, `None` for the tail.
+    pub fn segment_dir(&self, i: usize) -> Option<Direction> {
+        let next = self.cells.get(i + 1)?;
+        return Some(self.topology.dir_to(
+            &self.cells[i],
+            next,
+            self.occupied.get_width(),
+            self.occupied.get_height(),
+        ));
+    }
+
+    /// Direction the head last moved in.
+    pub fn heading(&self) -> Direction {
+        match self.segment_dir(0) {
+            Some(dir) => dir.oposite(),
+            None => Direction::RIGHT,
+        }
+    }
+
+    /// True right after eating, while the tail is held in place.
+    pub fn is_growing(&self) -> bool {
+        self.growth > 0
+    }
+
+    pub fn topology(&self) -> Topology {
+        self.topology
+    }
+
+    /// Number of moves the tail will stay put for.
+    pub fn growth(&self) -> usize {
+        self.growth
     }
 
     pub fn get_head(&self) -> &Point {
-        &self.head
+        &self.cells[0]
     }
     pub fn get_tail(&self) -> Point {
-        let mut tail = None;
-        self.for_each_segment(|pt, i| {
-            if i == self.segment_dirs.len() {
-                tail = Some(pt);
-            }
-        });
-
-        let tail = tail.expect("No Tail?");
-        return tail;
+        self.cells[self.cells.len() - 1]
     }
+    /// Segments behind the head, counting ones still to grow.
     pub fn size(&self) -> usize {
-        self.segment_dirs.len()
+        self.cells.len() - 1 + self.growth
     }
 
     pub fn expand(&mut self) {
-        self.segment_dirs
-            .push(self.segment_dirs.last().unwrap().oposite());
+        self.growth += 1;
     }
 }
@@ -0,0 +1,99 @@
+use crate::a_star::{a_star_search, SearchConfig, SearchStats};
+use crate::direction::Direction;
+use crate::error::GameError;
+use crate::point::Point;
+use crate::strategy::{GameView, Strategy};
+use rand::rngs::StdRng;
+
+/// Follows the shortest path to the nearest apple, recomputed each time an
+/// apple is eaten or the path is cut off.
+pub struct AStarStrategy {
+    config: SearchConfig,
+    path: Option<Vec<Point>>,
+    current: usize,
+    last_expanded: u64,
+    stats: SearchStats,
+}
+
+impl AStarStrategy {
+    pub fn new() -> AStarStrategy {
+        AStarStrategy::with_search(SearchConfig::default())
+    }
+
+    pub fn with_search(config: SearchConfig) -> AStarStrategy {
+        AStarStrategy {
+            config,
+            path: None,
+            current: 1,
+            last_expanded: 0,
+            stats: SearchStats::default(),
+        }
+    }
+
+    fn find_path(&mut self, view: &GameView) {
+        let result = a_star_search(view.apples, view.snake, view.walls, &self.config);
+        self.stats.record(&result);
+        self.last_expanded = result.expanded;
+        self.path = result.path;
+        self.current = 1;
+    }
+
+    /// True once the path runs out, its apple has gone or something stands
+    /// in the way of its next step, which only another snake can cause.
+    fn path_stale(&self, view: &GameView) -> bool {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return false,
+        };
+        match (path.get(self.current), path.last()) {
+            (Some(next), Some(end)) => !view.apples.contains(end) || view.walls[*next],
+            _ => true,
+        }
+    }
+}
+
+impl Default for AStarStrategy {
+    fn default() -> AStarStrategy {
+        AStarStrategy::new()
+    }
+}
+
+impl Strategy for AStarStrategy {
+    fn name(&self) -> &'static str {
+        "astar"
+    }
+
+    fn next_direction(&mut self, view: &GameView) -> Result<Direction, GameError> {
+        if self.path_stale(view) {
+            self.find_path(view);
+        }
+        let path = self.path.as_ref().ok_or(GameError::UnreachableApple)?;
+        let next_pt = path
+            .get(self.current)
+            .ok_or_else(|| GameError::strategy_failed(self.name(), "ran off the end of the path"))?;
+        self.current += 1;
+        Ok(view.dir_to(view.snake.get_head(), next_pt))
+    }
+
+    fn on_apple_eaten(&mut self, view: &GameView) {
+        self.find_path(view);
+    }
+
+    fn on_reset(&mut self, view: &GameView, _rng: &mut StdRng) -> Result<(), GameError> {
+        self.stats = SearchStats::default();
+        self.find_path(view);
+        if self.path.is_none() {
+            return Err(GameError::UnreachableApple);
+        }
+        Ok(())
+    }
+
+    fn status(&self, _view: &GameView) -> String {
+        let path_len = self.path.as_ref().map_or(0, |path| path.len().saturating_sub(1));
+        format!("PathLen={}, Expanded={}", path_len, self.last_expanded)
+    }
+
+    fn search_stats(&self) -> Option<SearchStats> {
+        Some(self.stats)
+    }
+}
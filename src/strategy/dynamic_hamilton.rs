@@ -0,0 +1,279 @@
+use crate::direction::Direction;
+use crate::error::GameError;
+use crate::hamiltonian_matrix::{CycleGenerator, HamiltonMatrix};
+use crate::matrix::Matrix;
+use crate::point::Point;
+use crate::strategy::hamilton::start_cycle;
+use crate::strategy::{GameView, Strategy};
+use rand::rngs::StdRng;
+
+/// Follows a hamiltonian cycle like `HamiltonStrategy`, skipping ahead along
+/// it the same way, but also rewires the part of the cycle ahead of the head
+/// so the apple comes up sooner.
+///
+/// Rewiring works on 2x2 squares of the board with two opposite sides on
+/// the cycle. Swapping those sides for the other two cuts the cycle in two,
+/// and a second such swap, on a square shared by both parts, joins them
+/// again. Each splice cuts a loop out of the cycle before the apple and joins
+/// it back in between the apple and the tail, bringing the apple nearer by
+/// the loop's length. The body, which trails behind the head in cycle order,
+/// is never touched.
+pub struct DynamicHamiltonStrategy {
+    generator: CycleGenerator,
+    ham_cycle: HamiltonMatrix,
+    /// Cell at each cycle position, the inverse of `ham_cycle`.
+    order: Vec<Point>,
+    splices: u64,
+}
+
+/// Free cells a shortcut must leave between the head and the tail, on top of
+/// any the tail still has to wait out while growing.
+const TAIL_MARGIN: usize = 5;
+
+/// Shortcuts are only taken while the snake covers less than one in this
+/// many cells. A shortcut leaves free cells behind the body that splices
+/// can't reach until the tail has passed them, so on a fuller board they
+/// cost more steps than they save, and if apples eat up the gap to the tail
+/// in the meantime the snake runs into itself.
+const SHORTCUT_FILL: usize = 4;
+
+/// True if `a` and `b` are one step apart, counting steps that wrap on a
+/// torus, so splices can use the wrapped edges too.
+fn is_adjacent(view: &GameView, a: &Point, b: &Point) -> bool {
+    view.topology.distance(a, b, view.board_width, view.board_height) == 1
+}
+
+impl DynamicHamiltonStrategy {
+    pub fn new() -> DynamicHamiltonStrategy {
+        DynamicHamiltonStrategy::with_cycle(CycleGenerator::Prim)
+    }
+
+    pub fn with_cycle(generator: CycleGenerator) -> DynamicHamiltonStrategy {
+        DynamicHamiltonStrategy {
+            generator,
+            ham_cycle: Matrix::new(0, 0),
+            order: vec![],
+            splices: 0,
+        }
+    }
+
+    /// Position of `pt` in `order`. Ids in `ham_cycle` run from 1, as 0
+    /// marks a wall, so they are one past the position.
+    fn cycle_id(&self, pt: &Point) -> Result<u32, GameError> {
+        self.ham_cycle.get(pt).cloned().filter(|id| *id != 0).map(|id| id - 1).ok_or_else(|| {
+            GameError::strategy_failed(self.name(), &format!("{:?} is not on the cycle", pt))
+        })
+    }
+
+    /// The cycle as a sequence starting at `start`.
+    fn cycle_from(&self, start: u32) -> Vec<Point> {
+        let start = start as usize;
+        let mut seq = Vec::with_capacity(self.order.len());
+        seq.extend_from_slice(&self.order[start..]);
+        seq.extend_from_slice(&self.order[..start]);
+        seq
+    }
+
+    fn set_cycle(&mut self, seq: Vec<Point>) {
+        for (id, pt) in seq.iter().enumerate() {
+            self.ham_cycle[*pt] = id as u32 + 1;
+        }
+        self.order = seq;
+    }
+
+    /// Steps along the cycle from the head, at `head_id`, to `pt`.
+    fn ahead(&self, pt: &Point, head_id: u32) -> Result<usize, GameError> {
+        let cells = self.order.len();
+        Ok((self.cycle_id(pt)? as usize + cells - head_id as usize) % cells)
+    }
+
+    /// Steps along the cycle from the head to the tail, if every segment
+    /// lies behind the one before it in cycle order, as it does once the
+    /// snake has followed the cycle, shortcuts and all, for its own length.
+    fn tail_ahead(&self, view: &GameView, head_id: u32) -> Result<Option<usize>, GameError> {
+        let mut last = self.order.len();
+        let mut in_order = true;
+        let mut failed = None;
+        view.snake.for_each_segment(|pt, i| {
+            if i == 0 || !in_order {
+                return;
+            }
+            match self.ahead(&pt, head_id) {
+                Ok(at) if at < last => last = at,
+                Ok(_) => in_order = false,
+                Err(err) => failed = Some(err),
+            }
+        });
+        if let Some(err) = failed {
+            return Err(err);
+        }
+        Ok(if in_order { Some(last) } else { None })
+    }
+
+    /// Splices the cycle until no splice brings the next apple along it
+    /// closer to the head. `seq` starts at the head.
+    fn repair(&mut self, view: &GameView, seq: &mut Vec<Point>, tail_ahead: usize) -> Result<(), GameError> {
+        let cells = seq.len();
+        let mut pos: Matrix<Option<usize>> = Matrix::new(view.board_width, view.board_height);
+        for (i, pt) in seq.iter().enumerate() {
+            pos[*pt] = Some(i);
+        }
+        let at = |pos: &Matrix<Option<usize>>, pt: Point| pos.get(&pt).cloned().flatten();
+
+        loop {
+            let mut apple_pos = cells;
+            for apple in view.apples {
+                let at = pos[*apple].ok_or_else(|| {
+                    GameError::strategy_failed(self.name(), "apple is not on the cycle")
+                })?;
+                apple_pos = apple_pos.min(at);
+            }
+            if apple_pos >= tail_ahead {
+                return Ok(());
+            }
+
+            // edges `m -> m + 1` before the apple that could be joined to an
+            // edge `k -> k + 1` between the apple and the tail
+            let mut joins: Vec<Option<usize>> = vec![None; apple_pos];
+            for m in 0..apple_pos - 1 {
+                for dir in Direction::all() {
+                    let k = match at(&pos, view.step(&seq[m + 1], dir)) {
+                        Some(k) => k,
+                        None => continue,
+                    };
+                    if k >= apple_pos && k < tail_ahead && is_adjacent(view, &seq[k + 1], &seq[m]) {
+                        joins[m] = Some(k);
+                    }
+                }
+            }
+            // joins before each position, to find one inside a loop quickly
+            let mut joins_before = vec![0; apple_pos + 1];
+            for m in 0..apple_pos {
+                joins_before[m + 1] = joins_before[m] + joins[m].is_some() as usize;
+            }
+
+            // the longest loop `i + 1 ..= j` that can be cut out before the
+            // apple and still be joined back in after it
+            let mut best: Option<(usize, usize)> = None;
+            for i in 0..apple_pos - 1 {
+                for dir in Direction::all() {
+                    let j = match at(&pos, view.step(&seq[i], dir)) {
+                        Some(after) if after > i + 3 && after <= apple_pos => after - 1,
+                        _ => continue,
+                    };
+                    if !is_adjacent(view, &seq[i + 1], &seq[j]) || joins_before[j] == joins_before[i + 1] {
+                        continue;
+                    }
+                    if best.map_or(true, |(best_i, best_j)| j - i > best_j - best_i) {
+                        best = Some((i, j));
+                    }
+                }
+            }
+
+            let (i, j, m, k) = match best.and_then(|(i, j)| (i + 1..j).find_map(|m| joins[m].map(|k| (i, j, m, k)))) {
+                Some(splice) => splice,
+                None => return Ok(()),
+            };
+
+            let mut spliced = Vec::with_capacity(cells);
+            spliced.extend_from_slice(&seq[..=i]);
+            spliced.extend_from_slice(&seq[j + 1..=k]);
+            spliced.extend_from_slice(&seq[m + 1..=j]);
+            spliced.extend_from_slice(&seq[i + 1..=m]);
+            spliced.extend_from_slice(&seq[k + 1..]);
+            for (idx, pt) in spliced.iter().enumerate().take(k + 1).skip(i + 1) {
+                pos[*pt] = Some(idx);
+            }
+            *seq = spliced;
+            self.splices += 1;
+        }
+    }
+}
+
+impl Default for DynamicHamiltonStrategy {
+    fn default() -> DynamicHamiltonStrategy {
+        DynamicHamiltonStrategy::new()
+    }
+}
+
+impl Strategy for DynamicHamiltonStrategy {
+    fn name(&self) -> &'static str {
+        "dynamic-hamilton"
+    }
+
+    fn next_direction(&mut self, view: &GameView) -> Result<Direction, GameError> {
+        let head = view.snake.get_head();
+        let head_id = self.cycle_id(head)?;
+        // until the body has settled onto the cycle a splice or a shortcut
+        // could cut through it, so keep following the cycle as generated
+        let tail_ahead = match self.tail_ahead(view, head_id)? {
+            Some(tail_ahead) => tail_ahead,
+            None => {
+                let next_id = (head_id as usize + 1) % self.order.len();
+                return Ok(view.dir_to(head, &self.order[next_id]));
+            }
+        };
+
+        // every step, as each move and each shortcut opens up new splices
+        let mut seq = self.cycle_from(head_id);
+        self.repair(view, &mut seq, tail_ahead)?;
+        self.set_cycle(seq);
+
+        let head_id = self.cycle_id(head)?;
+        let mut apple_ahead = self.order.len();
+        for apple in view.apples {
+            apple_ahead = apple_ahead.min(self.ahead(apple, head_id)?);
+        }
+
+        // the furthest neighbour along the cycle that doesn't pass the apple
+        // or come too close to the tail, which is at worst the next cell
+        let shortcuts = (view.snake.size() + 1) * SHORTCUT_FILL < self.order.len();
+        let mut best: Option<(usize, Direction)> = None;
+        for dir in Direction::all() {
+            let next = view.step(head, dir);
+            // off the board or a wall
+            if view.walls.get(&next) != Some(&false) {
+                continue;
+            }
+            let at = self.ahead(&next, head_id)?;
+            if at == 0 || at > apple_ahead.max(1) {
+                continue;
+            }
+            if at > 1 && (!shortcuts || at + view.snake.growth() + TAIL_MARGIN > tail_ahead) {
+                continue;
+            }
+            // a growing tail stays put for the move
+            if at == tail_ahead && view.snake.is_growing() {
+                continue;
+            }
+            if best.map_or(true, |(best_at, _)| at > best_at) {
+                best = Some((at, *dir));
+            }
+        }
+
+        best.map(|(_, dir)| dir).ok_or_else(|| {
+            GameError::strategy_failed(self.name(), "no neighbour continues the cycle")
+        })
+    }
+
+    fn on_reset(&mut self, view: &GameView, rng: &mut StdRng) -> Result<(), GameError> {
+        let seq = start_cycle(self.generator, view, rng)?;
+        self.ham_cycle = HamiltonMatrix::new(view.board_width, view.board_height);
+        self.set_cycle(seq);
+        self.splices = 0;
+        Ok(())
+    }
+
+    fn status(&self, view: &GameView) -> String {
+        let ham_v = self.cycle_id(view.snake.get_head()).unwrap_or(0);
+        let cells = self.order.len().max(1) as u32;
+        let apple_dist = view
+            .apples
+            .iter()
+            .map(|apple| (self.cycle_id(apple).unwrap_or(0) + cells - ham_v) % cells)
+            .min()
+            .unwrap_or(0);
+
+        format!("HamV={:03}, AppleDist={:03}, Splices={}", ham_v, apple_dist, self.splices)
+    }
+}
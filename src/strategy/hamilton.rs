@@ -0,0 +1,259 @@
+use crate::direction::Direction;
+use crate::error::GameError;
+use crate::hamiltonian_matrix::{CycleGenerator, HamiltonMatrix};
+use crate::matrix::Matrix;
+use crate::point::Point;
+use crate::strategy::{GameView, Strategy};
+use rand::rngs::StdRng;
+
+/// Follows a random hamiltonian cycle, skipping ahead along it whenever that
+/// gets the head closer to the apple without risking the body.
+///
+/// The cycle covers the whole board, so in an arena it takes no notice of
+/// the other snakes beyond not skipping onto them, and the head runs into any
+/// that cross the cycle ahead of it.
+pub struct HamiltonStrategy {
+    generator: CycleGenerator,
+    ham_cycle: HamiltonMatrix,
+    /// Cells on the cycle, every one but the walls.
+    cycle_len: u32,
+}
+
+/// True if a snake following `seq` from its head gets clear of the cells it
+/// started on without running into itself.
+pub(crate) fn clears_body(view: &GameView, seq: &[Point]) -> bool {
+    let start = match seq.iter().position(|pt| pt == view.snake.get_head()) {
+        Some(start) => start,
+        None => return false,
+    };
+
+    let mut snake = view.snake.clone();
+    for k in 1..=snake.size() {
+        let next = seq[(start + k) % seq.len()];
+        snake.move_dir(&view.dir_to(snake.get_head(), &next));
+        if snake.check_collide() {
+            return false;
+        }
+    }
+    true
+}
+
+/// Cycles laid out before settling for one the starting snake can't follow.
+const START_ATTEMPTS: usize = 8;
+
+/// A cycle as its cells in order, run the way that takes the starting snake
+/// clear of the cells it starts on. The snake doesn't start on the cycle, so
+/// when neither way does, another cycle is laid out in its place.
+pub(crate) fn start_cycle(generator: CycleGenerator, view: &GameView, rng: &mut StdRng) -> Result<Vec<Point>, GameError> {
+    let mut seq = vec![];
+    for _ in 0..START_ATTEMPTS {
+        let ham_cycle = HamiltonMatrix::generate_around(generator, view.walls, rng)?;
+        // walls are left at 0
+        let mut cells: Vec<(u32, Point)> = ham_cycle
+            .enumerate()
+            .filter(|(_, id)| **id != 0)
+            .map(|(pt, id)| (*id, pt))
+            .collect();
+        cells.sort_by_key(|(id, _)| *id);
+        seq = cells.into_iter().map(|(_, pt)| pt).collect();
+        if clears_body(view, &seq) {
+            return Ok(seq);
+        }
+        seq.reverse();
+        if clears_body(view, &seq) {
+            return Ok(seq);
+        }
+    }
+    Ok(seq)
+}
+
+impl HamiltonStrategy {
+    pub fn new() -> HamiltonStrategy {
+        HamiltonStrategy::with_cycle(CycleGenerator::Prim)
+    }
+
+    pub fn with_cycle(generator: CycleGenerator) -> HamiltonStrategy {
+        HamiltonStrategy {
+            generator,
+            ham_cycle: Matrix::new(0, 0),
+            cycle_len: 0,
+        }
+    }
+
+    fn cycle_id(&self, pt: &Point) -> Result<u32, GameError> {
+        self.ham_cycle.get(pt).cloned().filter(|id| *id != 0).ok_or_else(|| {
+            GameError::strategy_failed(self.name(), &format!("{:?} is not on the cycle", pt))
+        })
+    }
+
+    /// Id of the first apple the head comes to following the cycle.
+    fn next_apple_id(&self, view: &GameView, head_id: u32) -> Result<u32, GameError> {
+        let board_max = self.cycle_len;
+        let mut nearest: Option<u32> = None;
+        for apple in view.apples {
+            let id = self.cycle_id(apple)?;
+            let ahead = |id: u32| (id + board_max - head_id) % board_max;
+            if nearest.map_or(true, |best| ahead(id) < ahead(best)) {
+                nearest = Some(id);
+            }
+        }
+        nearest.ok_or_else(|| GameError::strategy_failed(self.name(), "no apples on the board"))
+    }
+
+    fn is_dir_next(&self, view: &GameView, currid: &u32, dir: &Direction) -> bool {
+        let test_pt = view.step(view.snake.get_head(), dir);
+
+        if let Some(other) = self.ham_cycle.get(&test_pt) {
+            if other == &(currid + 1) {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// True once every segment lies behind the one before it along the
+    /// cycle, as it does after the snake has followed the cycle for its own
+    /// length.
+    fn body_settled(&self, view: &GameView, head_id: u32) -> bool {
+        let board_max = self.cycle_len;
+        let mut last = 0;
+        let mut settled = true;
+        view.snake.for_each_segment(|pt, i| {
+            if i == 0 || !settled {
+                return;
+            }
+            match self.cycle_id(&pt) {
+                Ok(id) if (head_id + board_max - id) % board_max > last => {
+                    last = (head_id + board_max - id) % board_max;
+                }
+                _ => settled = false,
+            }
+        });
+        settled
+    }
+
+    fn tail_mod_dist(&self, view: &GameView, test_val: u32) -> Result<i32, GameError> {
+        let board_max = self.cycle_len;
+        let tail_id = self.cycle_id(&view.snake.get_tail())?;
+        let head_id = self.cycle_id(view.snake.get_head())?;
+
+        let test_dist = if (tail_id < head_id && tail_id < test_val && head_id > test_val)
+            || (tail_id > head_id && tail_id < test_val && head_id < test_val)
+        {
+            -1
+        } else if tail_id < test_val {
+            ((board_max - test_val) + tail_id) as i32
+        } else {
+            (tail_id - test_val) as i32
+        };
+
+        // a growing tail holds still, so it's that many moves nearer
+        if test_dist > 0 {
+            return Ok(test_dist - view.snake.growth() as i32);
+        }
+        Ok(test_dist)
+    }
+}
+
+impl Default for HamiltonStrategy {
+    fn default() -> HamiltonStrategy {
+        HamiltonStrategy::new()
+    }
+}
+
+impl Strategy for HamiltonStrategy {
+    fn name(&self) -> &'static str {
+        "hamilton"
+    }
+
+    fn next_direction(&mut self, view: &GameView) -> Result<Direction, GameError> {
+        let currid = self.cycle_id(view.snake.get_head())?;
+        let apple_val = self.next_apple_id(view, currid)?;
+
+        let mut closest_path: Option<(u32, Direction)> = None;
+        // until the body has settled onto the cycle a skip could cut back
+        // through it, so keep to the cycle
+        let settled = self.body_settled(view, currid);
+        for dir in Direction::all().iter().filter(|_| settled) {
+            let test_pt = view.step(view.snake.get_head(), dir);
+
+            if let Some(other) = self.ham_cycle.get(&test_pt) {
+                // don't go past apple
+                if apple_val > currid && other > &apple_val {
+                    continue;
+                }
+                //    dont go backwards
+                if other < &currid {
+                    continue;
+                }
+
+                // get largest value
+                if closest_path.is_some() && &closest_path.unwrap().0 > other {
+                    continue;
+                }
+
+                // try not to turn into self
+                if view.snake.on_snake(test_pt) {
+                    continue;
+                }
+
+                // or into a wall, which in an arena includes the other snakes
+                if view.walls[test_pt] {
+                    continue;
+                }
+
+                // don't jump too close to tail
+                if self.tail_mod_dist(view, *other)? < 5 {
+                    continue;
+                }
+
+                closest_path = Some((*other, *dir));
+            }
+        }
+
+        if let Some((_, dir)) = closest_path {
+            return Ok(dir);
+        }
+
+        // Check for next num in cycle
+        for dir in Direction::all() {
+            if self.is_dir_next(view, &currid, dir) {
+                return Ok(*dir);
+            }
+        }
+
+        // if we hit end, find pt 1
+        for dir in Direction::all() {
+            if self.is_dir_next(view, &0, dir) {
+                return Ok(*dir);
+            }
+        }
+
+        Err(GameError::strategy_failed(
+            self.name(),
+            "no neighbour continues the cycle",
+        ))
+    }
+
+    fn on_reset(&mut self, view: &GameView, rng: &mut StdRng) -> Result<(), GameError> {
+        let seq = start_cycle(self.generator, view, rng)?;
+        self.ham_cycle = Matrix::new(view.board_width, view.board_height);
+        for (i, pt) in seq.iter().enumerate() {
+            self.ham_cycle[*pt] = i as u32 + 1;
+        }
+        self.cycle_len = seq.len() as u32;
+        Ok(())
+    }
+
+    fn status(&self, view: &GameView) -> String {
+        let ham_v = self.cycle_id(view.snake.get_head()).unwrap_or(0);
+        let apple_v = self.next_apple_id(view, ham_v).unwrap_or(0);
+
+        format!(
+            "HamV={:03}, AppleV={:03}, HeadTail={:03}",
+            ham_v,
+            apple_v,
+            self.tail_mod_dist(view, ham_v).unwrap_or(0)
+        )
+    }
+}
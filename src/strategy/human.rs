@@ -0,0 +1,75 @@
+use crate::direction::Direction;
+use crate::error::GameError;
+use crate::strategy::{GameView, Strategy};
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+/// Turns queued up beyond this many are dropped, so mashing keys can't leave
+/// the snake working through a backlog long after they were pressed.
+const TURN_BUFFER: usize = 3;
+
+/// Turns pressed but not yet taken, shared between whatever reads the keys
+/// and the `HumanStrategy` steering the snake. Clones share one queue.
+#[derive(Debug, Clone, Default)]
+pub struct TurnQueue {
+    turns: Arc<Mutex<VecDeque<Direction>>>,
+}
+
+impl TurnQueue {
+    pub fn new() -> TurnQueue {
+        TurnQueue::default()
+    }
+
+    pub fn push(&self, dir: Direction) {
+        let mut turns = self.turns.lock().unwrap();
+        if turns.len() < TURN_BUFFER {
+            turns.push_back(dir);
+        }
+    }
+
+    fn pop(&self) -> Option<Direction> {
+        self.turns.lock().unwrap().pop_front()
+    }
+
+    pub fn len(&self) -> usize {
+        self.turns.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Steered from the keyboard. Each tick takes one queued turn, skipping any
+/// that would keep the heading or double back into the neck, and otherwise
+/// carries straight on.
+pub struct HumanStrategy {
+    turns: TurnQueue,
+}
+
+impl HumanStrategy {
+    pub fn new(turns: TurnQueue) -> HumanStrategy {
+        HumanStrategy { turns }
+    }
+}
+
+impl Strategy for HumanStrategy {
+    fn name(&self) -> &'static str {
+        "human"
+    }
+
+    fn next_direction(&mut self, view: &GameView) -> Result<Direction, GameError> {
+        let heading = view.snake.heading();
+        while let Some(turn) = self.turns.pop() {
+            if turn != heading && turn != heading.oposite() {
+                return Ok(turn);
+            }
+        }
+        Ok(heading)
+    }
+
+    fn status(&self, _view: &GameView) -> String {
+        format!("Queued={}", self.turns.len())
+    }
+}
@@ -0,0 +1,114 @@
+mod astar;
+mod dynamic_hamilton;
+mod hamilton;
+mod human;
+mod neural;
+mod safe_astar;
+
+pub use self::astar::AStarStrategy;
+pub use self::dynamic_hamilton::DynamicHamiltonStrategy;
+pub use self::hamilton::HamiltonStrategy;
+pub use self::human::{HumanStrategy, TurnQueue};
+pub use self::neural::NeuralStrategy;
+pub use self::safe_astar::SafeAStarStrategy;
+
+use crate::a_star::{SearchConfig, SearchStats};
+use crate::direction::Direction;
+use crate::error::GameError;
+use crate::hamiltonian_matrix::CycleGenerator;
+use crate::matrix::Matrix;
+use crate::neural::Network;
+use crate::point::Point;
+use crate::snake::Snake;
+use crate::topology::Topology;
+use rand::rngs::StdRng;
+
+/// Read-only view of the game handed to a strategy each time it is asked
+/// for a move.
+pub struct GameView<'a> {
+    pub snake: &'a Snake,
+    /// Every apple on the board, in no particular order.
+    pub apples: &'a [Point],
+    /// Cells inside the board the snake can't enter.
+    pub walls: &'a Matrix<bool>,
+    pub board_width: usize,
+    pub board_height: usize,
+    pub topology: Topology,
+}
+
+impl GameView<'_> {
+    /// The cell one step from `pt` in `dir`, wrapping on a torus.
+    pub fn step(&self, pt: &Point, dir: &Direction) -> Point {
+        self.topology.step(pt, dir, self.board_width, self.board_height)
+    }
+
+    /// Direction of the step from `from` to its neighbour `to`.
+    pub fn dir_to(&self, from: &Point, to: &Point) -> Direction {
+        self.topology.dir_to(from, to, self.board_width, self.board_height)
+    }
+}
+
+/// A pathing AI. The game asks it for a direction once per tick and tells it
+/// when the board changes underneath it.
+pub trait Strategy {
+    fn name(&self) -> &'static str;
+
+    /// Picks the next direction for the snake's head.
+    fn next_direction(&mut self, view: &GameView) -> Result<Direction, GameError>;
+
+    /// Called after the snake has eaten and a replacement apple has been
+    /// placed.
+    fn on_apple_eaten(&mut self, _view: &GameView) {}
+
+    /// Called once the board is set up, before the first move. Any
+    /// randomness the strategy needs must come from `rng`.
+    fn on_reset(&mut self, _view: &GameView, _rng: &mut StdRng) -> Result<(), GameError> {
+        Ok(())
+    }
+
+    /// Extra information for the HUD.
+    fn status(&self, _view: &GameView) -> String {
+        String::new()
+    }
+
+    /// Totals over every path search made since the last reset, for
+    /// strategies that search.
+    fn search_stats(&self) -> Option<SearchStats> {
+        None
+    }
+}
+
+/// Settings for strategies that take them. Strategies ignore whatever doesn't
+/// apply to them.
+#[derive(Debug, Clone, Default)]
+pub struct StrategyOptions {
+    /// How cycle following strategies lay out their cycle.
+    pub cycle: CycleGenerator,
+    /// How searching strategies run A*.
+    pub search: SearchConfig,
+    /// Where the human strategy takes its turns from.
+    pub turns: TurnQueue,
+    /// The trained network the neural strategy plays with.
+    pub network: Option<Network>,
+}
+
+pub const STRATEGY_NAMES: [&str; 6] = ["hamilton", "dynamic-hamilton", "astar", "safe-astar", "human", "neural"];
+
+pub fn from_name(name: &str) -> Result<Box<dyn Strategy>, GameError> {
+    build(name, &StrategyOptions::default())
+}
+
+pub fn build(name: &str, options: &StrategyOptions) -> Result<Box<dyn Strategy>, GameError> {
+    match name {
+        "astar" => Ok(Box::new(AStarStrategy::with_search(options.search))),
+        "hamilton" => Ok(Box::new(HamiltonStrategy::with_cycle(options.cycle))),
+        "dynamic-hamilton" => Ok(Box::new(DynamicHamiltonStrategy::with_cycle(options.cycle))),
+        "safe-astar" => Ok(Box::new(SafeAStarStrategy::with_search(options.search))),
+        "human" => Ok(Box::new(HumanStrategy::new(options.turns.clone()))),
+        "neural" => match &options.network {
+            Some(network) => Ok(Box::new(NeuralStrategy::new(network.clone()))),
+            None => Err(GameError::strategy_failed("neural", "no network given")),
+        },
+        _ => Err(GameError::UnknownStrategy(name.to_string())),
+    }
+}
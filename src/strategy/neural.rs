@@ -0,0 +1,57 @@
+use crate::direction::Direction;
+use crate::error::GameError;
+use crate::neural::Network;
+use crate::strategy::{GameView, Strategy};
+use rand::rngs::StdRng;
+
+/// Moves wherever a trained network says, given the board in the network's
+/// encoding. A move back into the neck carries on straight, as it does for
+/// a player.
+pub struct NeuralStrategy {
+    network: Network,
+}
+
+impl NeuralStrategy {
+    pub fn new(network: Network) -> NeuralStrategy {
+        NeuralStrategy { network }
+    }
+}
+
+impl Strategy for NeuralStrategy {
+    fn name(&self) -> &'static str {
+        "neural"
+    }
+
+    fn next_direction(&mut self, view: &GameView) -> Result<Direction, GameError> {
+        let features = self.network.encoding().encode(view);
+        let action = self.network.action(&features);
+        let heading = view.snake.heading();
+        let dir = self
+            .network
+            .actions()
+            .direction(action, heading)
+            .ok_or_else(|| GameError::strategy_failed(self.name(), "network chose an action that doesn't exist"))?;
+        if dir == heading.oposite() {
+            return Ok(heading);
+        }
+        Ok(dir)
+    }
+
+    fn on_reset(&mut self, view: &GameView, _rng: &mut StdRng) -> Result<(), GameError> {
+        let size = self
+            .network
+            .encoding()
+            .size(view.board_width, view.board_height);
+        if size != self.network.inputs() {
+            return Err(GameError::strategy_failed(
+                self.name(),
+                &format!(
+                    "network takes {} inputs but the board encodes to {}",
+                    self.network.inputs(),
+                    size
+                ),
+            ));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,297 @@
+use crate::a_star::{search, SearchConfig, SearchStats};
+use crate::direction::Direction;
+use crate::error::GameError;
+use crate::matrix::Matrix;
+use crate::point::Point;
+use crate::snake::Snake;
+use crate::strategy::{GameView, Strategy};
+use crate::topology::Topology;
+use rand::rngs::StdRng;
+
+use std::collections::VecDeque;
+
+/// A* that only takes a path to the apple if, after eating it, the snake
+/// could still follow its own tail. Otherwise it chases its tail, and failing
+/// that moves wherever leaves it the most room.
+pub struct SafeAStarStrategy {
+    config: SearchConfig,
+    last_move: &'static str,
+    stats: SearchStats,
+}
+
+/// Cells of `snake` and the walls as a blocked matrix. The tail is left open
+/// unless the snake is growing, since it moves out of the way on the next
+/// step.
+fn body_cells(snake: &Snake, walls: &Matrix<bool>) -> Matrix<bool> {
+    let mut blocked = walls.clone();
+    snake.for_each_segment(|pt, _| {
+        blocked[pt] = true;
+    });
+    if !snake.is_growing() {
+        blocked[snake.get_tail()] = false;
+    }
+    blocked
+}
+
+/// Number of open cells reachable from `start`.
+fn reachable_area(start: &Point, blocked: &Matrix<bool>, topology: Topology) -> usize {
+    let mut seen: Matrix<bool> = Matrix::new(blocked.get_width(), blocked.get_height());
+    let mut stack = vec![*start];
+    seen[*start] = true;
+
+    let mut area = 0;
+    while let Some(current) = stack.pop() {
+        area += 1;
+        for next in topology.neighbours(current, blocked.get_width(), blocked.get_height()) {
+            if blocked[next] || seen[next] {
+                continue;
+            }
+            seen[next] = true;
+            stack.push(next);
+        }
+    }
+
+    area
+}
+
+/// A way from the head of `snake` onto a cell of its body that the body has
+/// left by the time the head gets there, found breadth first. Following the
+/// body from there on is always safe. A segment is left `growth + 1` moves
+/// after the tail's turn comes, plus one move for every segment behind it.
+fn follow_path(snake: &Snake, walls: &Matrix<bool>, topology: Topology) -> Option<Vec<Point>> {
+    let width = walls.get_width();
+    let height = walls.get_height();
+    let mut free_after: Matrix<usize> = Matrix::new(width, height);
+    let mut segments = 0;
+    snake.for_each_segment(|_, _| segments += 1);
+    snake.for_each_segment(|pt, i| free_after[pt] = snake.growth() + segments - i);
+
+    let head = *snake.get_head();
+    let mut came_from: Matrix<Option<Point>> = Matrix::new(width, height);
+    let mut queue = VecDeque::new();
+    queue.push_back((head, 0));
+    while let Some((pt, moves)) = queue.pop_front() {
+        for next in topology.neighbours(pt, width, height) {
+            if walls[next] || next == head || came_from[next].is_some() || free_after[next] > moves + 1 {
+                continue;
+            }
+            came_from[next] = Some(pt);
+            if free_after[next] > 0 {
+                let mut path = vec![next];
+                while let Some(prev) = came_from[*path.last().unwrap_or(&head)] {
+                    path.push(prev);
+                    if prev == head {
+                        break;
+                    }
+                }
+                path.reverse();
+                return Some(path);
+            }
+            queue.push_back((next, moves + 1));
+        }
+    }
+    None
+}
+
+impl SafeAStarStrategy {
+    pub fn new() -> SafeAStarStrategy {
+        SafeAStarStrategy::with_search(SearchConfig::default())
+    }
+
+    pub fn with_search(config: SearchConfig) -> SafeAStarStrategy {
+        SafeAStarStrategy {
+            config,
+            last_move: "none",
+            stats: SearchStats::default(),
+        }
+    }
+
+    fn find_path(
+        &mut self,
+        start: &Point,
+        ends: &[Point],
+        blocked: &Matrix<bool>,
+        topology: Topology,
+    ) -> Option<Vec<Point>> {
+        let result = search(start, ends, blocked, topology, &self.config);
+        self.stats.record(&result);
+        result.path
+    }
+
+    /// Path from the head of `snake` onto its tail. While growing the tail
+    /// stays put, so the head needs room to go around before stepping onto
+    /// it.
+    fn tail_path(&mut self, snake: &Snake, view: &GameView) -> Option<Vec<Point>> {
+        let mut blocked = body_cells(snake, view.walls);
+        let tail = snake.get_tail();
+        blocked[tail] = false;
+
+        let path = self.find_path(snake.get_head(), &[tail], &blocked, view.topology)?;
+        if path.len() < 2 + snake.growth() {
+            return None;
+        }
+        Some(path)
+    }
+
+    /// Follows `path` with a copy of the snake, eating at the end, and checks
+    /// the copy's head can still reach its tail.
+    fn path_is_safe(&mut self, view: &GameView, path: &[Point]) -> bool {
+        let mut virtual_snake = view.snake.clone();
+        for pair in path.windows(2) {
+            virtual_snake.move_dir(&view.dir_to(&pair[0], &pair[1]));
+        }
+        virtual_snake.expand();
+
+        self.tail_path(&virtual_snake, view).is_some()
+    }
+
+    /// A copy of the snake moved one step in `dir`, grown if it lands on an
+    /// apple just as the game would grow it.
+    fn step_snake(view: &GameView, dir: &Direction) -> Snake {
+        let mut virtual_snake = view.snake.clone();
+        virtual_snake.move_dir(dir);
+        if view.apples.contains(virtual_snake.get_head()) {
+            virtual_snake.expand();
+        }
+        virtual_snake
+    }
+
+    fn apple_move(&mut self, view: &GameView, blocked: &Matrix<bool>) -> Option<Direction> {
+        let path = self.find_path(view.snake.get_head(), view.apples, blocked, view.topology)?;
+        if path.len() < 2 || !self.path_is_safe(view, &path) {
+            return None;
+        }
+        Some(view.dir_to(view.snake.get_head(), &path[1]))
+    }
+
+    /// Of the moves that keep the tail reachable, takes the one furthest from
+    /// it, so the snake wanders instead of circling tightly. If the tail is
+    /// too close to wait out the growth, settles for a move that can follow
+    /// the body onto a cell it will have left.
+    fn tail_move(&mut self, view: &GameView, blocked: &Matrix<bool>) -> Option<Direction> {
+        let mut best: Option<((bool, usize), Direction)> = None;
+        for dir in Direction::all() {
+            let next = view.step(view.snake.get_head(), dir);
+            if blocked.get(&next) != Some(&false) {
+                continue;
+            }
+
+            let virtual_snake = SafeAStarStrategy::step_snake(view, dir);
+            let rank = match self.tail_path(&virtual_snake, view) {
+                Some(path) => (true, path.len()),
+                None => match follow_path(&virtual_snake, view.walls, view.topology) {
+                    Some(path) => (false, path.len()),
+                    None => continue,
+                },
+            };
+            if best.map_or(true, |(best_rank, _)| rank > best_rank) {
+                best = Some((rank, *dir));
+            }
+        }
+        best.map(|(_, dir)| dir)
+    }
+
+    /// Of the moves that don't die at once, takes the one leaving the most
+    /// open cells reachable from the new head, counting the body as it will
+    /// be after the move.
+    fn roomiest_move(&self, view: &GameView, blocked: &Matrix<bool>) -> Option<Direction> {
+        let mut best: Option<(usize, Direction)> = None;
+        for dir in Direction::all() {
+            let next = view.step(view.snake.get_head(), dir);
+            // off the board or on the body
+            if blocked.get(&next) != Some(&false) {
+                continue;
+            }
+
+            let virtual_snake = SafeAStarStrategy::step_snake(view, dir);
+            let area = reachable_area(&next, &body_cells(&virtual_snake, view.walls), view.topology);
+            if best.map_or(true, |(best_area, _)| area > best_area) {
+                best = Some((area, *dir));
+            }
+        }
+        best.map(|(_, dir)| dir)
+    }
+}
+
+impl Default for SafeAStarStrategy {
+    fn default() -> SafeAStarStrategy {
+        SafeAStarStrategy::new()
+    }
+}
+
+impl Strategy for SafeAStarStrategy {
+    fn name(&self) -> &'static str {
+        "safe-astar"
+    }
+
+    fn next_direction(&mut self, view: &GameView) -> Result<Direction, GameError> {
+        let blocked = body_cells(view.snake, view.walls);
+
+        if let Some(dir) = self.apple_move(view, &blocked) {
+            self.last_move = "apple";
+            return Ok(dir);
+        }
+
+        if let Some(dir) = self.tail_move(view, &blocked) {
+            self.last_move = "tail";
+            return Ok(dir);
+        }
+
+        if let Some(dir) = self.roomiest_move(view, &blocked) {
+            self.last_move = "room";
+            return Ok(dir);
+        }
+
+        // boxed in, every move is fatal
+        self.last_move = "none";
+        Err(GameError::UnreachableApple)
+    }
+
+    fn on_reset(&mut self, _view: &GameView, _rng: &mut StdRng) -> Result<(), GameError> {
+        self.stats = SearchStats::default();
+        Ok(())
+    }
+
+    fn status(&self, _view: &GameView) -> String {
+        format!("Move={}, Searches={}", self.last_move, self.stats.searches)
+    }
+
+    fn search_stats(&self) -> Option<SearchStats> {
+        Some(self.stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn growing_snake_next_to_its_tail_follows_its_body() {
+        let walls: Matrix<bool> = Matrix::new(6, 6);
+        let mut snake = Snake::new(Point::new(3, 1), 3, 6, 6, Topology::Bounded);
+        snake.move_dir(&Direction::DOWN);
+        snake.move_dir(&Direction::LEFT);
+        // head on (2, 2), right below the tail, with more segments still to
+        // grow than the shortest way round to it is long
+        for _ in 0..6 {
+            snake.expand();
+        }
+        assert_eq!(snake.get_tail(), Point::new(2, 1));
+
+        let mut strategy = SafeAStarStrategy::new();
+        for _ in 0..30 {
+            let view = GameView {
+                snake: &snake,
+                apples: &[],
+                walls: &walls,
+                board_width: 6,
+                board_height: 6,
+                topology: Topology::Bounded,
+            };
+            let dir = strategy.next_direction(&view).expect("a safe move");
+            assert_ne!(strategy.last_move, "room");
+            snake.move_dir(&dir);
+            assert!(walls.get(snake.get_head()).is_some() && !snake.check_collide());
+        }
+    }
+}
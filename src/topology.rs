@@ -0,0 +1,73 @@
+use crate::direction::Direction;
+use crate::point::Point;
+
+/// What happens at the edges of the board.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Topology {
+    /// The edges are walls.
+    #[default]
+    Bounded,
+    /// Each edge wraps round to the opposite one.
+    Torus,
+}
+
+pub const TOPOLOGY_NAMES: [&str; 2] = ["bounded", "torus"];
+
+impl Topology {
+    pub fn from_name(name: &str) -> Option<Topology> {
+        match name {
+            "bounded" => Some(Topology::Bounded),
+            "torus" => Some(Topology::Torus),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Topology::Bounded => "bounded",
+            Topology::Torus => "torus",
+        }
+    }
+
+    /// The cell one step from `pt` in `dir` on a `width` by `height` board.
+    /// On a bounded board that can be off the edge.
+    pub fn step(&self, pt: &Point, dir: &Direction, width: usize, height: usize) -> Point {
+        let next = pt.dir_adj(dir);
+        match self {
+            Topology::Bounded => next,
+            Topology::Torus => Point::new(
+                next.x.rem_euclid(width as i32),
+                next.y.rem_euclid(height as i32),
+            ),
+        }
+    }
+
+    /// The up to four cells one step from `pt` that are on the board.
+    pub fn neighbours(&self, pt: Point, width: usize, height: usize) -> impl Iterator<Item = Point> {
+        let topology = *self;
+        Direction::all()
+            .iter()
+            .map(move |dir| topology.step(&pt, dir, width, height))
+            .filter(move |next| next.x >= 0 && next.y >= 0 && next.x < width as i32 && next.y < height as i32)
+    }
+
+    /// Direction of the step from `from` to its neighbour `to`.
+    pub fn dir_to(&self, from: &Point, to: &Point, width: usize, height: usize) -> Direction {
+        for dir in Direction::all() {
+            if self.step(from, dir, width, height) == *to {
+                return *dir;
+            }
+        }
+        from.in_dir(to)
+    }
+
+    /// Fewest steps from `a` to `b` with nothing in the way.
+    pub fn distance(&self, a: &Point, b: &Point, width: usize, height: usize) -> u32 {
+        let dx = (a.x - b.x).unsigned_abs();
+        let dy = (a.y - b.y).unsigned_abs();
+        match self {
+            Topology::Bounded => dx + dy,
+            Topology::Torus => dx.min(width as u32 - dx) + dy.min(height as u32 - dy),
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use crate::error::GameError;
+use crate::matrix::Matrix;
+use crate::point::Point;
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+/// Reads a map drawn with `#` for walls and `.` for open cells, one line per
+/// row. Every row has to be the same width.
+pub fn parse_map(text: &str) -> Result<Matrix<bool>, GameError> {
+    let rows: Vec<&str> = text.lines().map(|line| line.trim_end()).filter(|line| !line.is_empty()).collect();
+    let width = match rows.first() {
+        Some(row) => row.chars().count(),
+        None => return Err(GameError::InvalidMap("map is empty".to_string())),
+    };
+
+    let mut walls = Matrix::new(width, rows.len());
+    for (y, row) in rows.iter().enumerate() {
+        if row.chars().count() != width {
+            return Err(GameError::InvalidMap(format!(
+                "row {} is {} wide, the first is {}",
+                y + 1,
+                row.chars().count(),
+                width
+            )));
+        }
+        for (x, c) in row.chars().enumerate() {
+            walls[Point::new(x as i32, y as i32)] = match c {
+                '#' => true,
+                '.' => false,
+                _ => {
+                    return Err(GameError::InvalidMap(format!(
+                        "unexpected '{}' at column {} of row {}",
+                        c,
+                        x + 1,
+                        y + 1
+                    )))
+                }
+            };
+        }
+    }
+    Ok(walls)
+}
+
+/// The 2x2 block of cells covering maze node `node`.
+fn block_cells(node: Point) -> [Point; 4] {
+    let corner = Point::new(node.x * 2, node.y * 2);
+    [corner, corner.add(1, 0), corner.add(0, 1), corner.add(1, 1)]
+}
+
+/// True if every maze node without a wall in it can reach every other.
+fn nodes_connected(walls: &Matrix<bool>) -> bool {
+    let open = Matrix::from_fn(walls.get_width() / 2, walls.get_height() / 2, |node| {
+        block_cells(node).iter().all(|pt| !walls[*pt])
+    });
+
+    let start = match open.enumerate().find(|(_, open)| **open) {
+        Some((start, _)) => start,
+        None => return true,
+    };
+    let mut seen: Matrix<bool> = Matrix::new(open.get_width(), open.get_height());
+    seen[start] = true;
+    let mut stack = vec![start];
+    let mut reached = 0;
+    while let Some(current) = stack.pop() {
+        reached += 1;
+        for next in open.neighbours(current) {
+            if open[next] && !seen[next] {
+                seen[next] = true;
+                stack.push(next);
+            }
+        }
+    }
+    reached == open.cells().filter(|open| **open).count()
+}
+
+/// Walls `count` random 2x2 blocks, lined up with the maze the hamiltonian
+/// cycles are grown over so a cycle can still go around them. Blocks stay
+/// clear of `keep_clear` and its neighbours, off the maze edge beside an odd
+/// column or row, which is spliced onto the cycle from there, and never cut
+/// the open maze in two. Returns how many blocks fitted.
+pub fn add_random_blocks<R: Rng>(walls: &mut Matrix<bool>, count: usize, keep_clear: &[Point], rng: &mut R) -> usize {
+    let near_kept = |pt: &Point| {
+        keep_clear
+            .iter()
+            .any(|kept| (kept.x - pt.x).abs() <= 1 && (kept.y - pt.y).abs() <= 1)
+    };
+
+    let maze_width = walls.get_width() / 2;
+    let maze_height = walls.get_height() / 2;
+    let odd_column = walls.get_width() % 2 != 0;
+    let odd_row = walls.get_height() % 2 != 0;
+    let mut nodes: Vec<Point> = Matrix::<bool>::new(maze_width, maze_height)
+        .points()
+        .filter(|node| !(odd_column && node.x as usize == maze_width - 1))
+        .filter(|node| !(odd_row && node.y as usize == maze_height - 1))
+        .collect();
+    nodes.shuffle(rng);
+
+    let mut placed = 0;
+    for node in nodes {
+        if placed == count {
+            break;
+        }
+        let cells = block_cells(node);
+        if cells.iter().any(|pt| walls[*pt] || near_kept(pt)) {
+            continue;
+        }
+
+        for pt in &cells {
+            walls[*pt] = true;
+        }
+        if !nodes_connected(walls) {
+            for pt in &cells {
+                walls[*pt] = false;
+            }
+            continue;
+        }
+        placed += 1;
+    }
+    placed
+}